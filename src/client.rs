@@ -4,10 +4,10 @@ use crate::support::*;
 use base64::engine::{general_purpose, Engine};
 pub use http_body_util::{BodyExt, Empty, Full};
 pub use hyper::{
-    body::{Body, Bytes},
+    body::{Body, Bytes, Incoming},
     client::conn::http1::handshake,
     header::{HeaderMap, HeaderName, HeaderValue},
-    Method, Request, Uri,
+    Method, Request, Response, Uri,
 };
 pub use serde::{Deserialize, Serialize};
 use std::env;
@@ -45,13 +45,16 @@ impl Client {
     }
 
     pub fn format_address(&self) -> String {
-        // unwrap warranted because the client is always built with the BASE_URL
+        // unwrap warranted because the client is always built with an absolute url
         let host = self.url.host().unwrap();
-        let addr = format!("{}:{}", host, "443");
+        let port = self.url.port_u16().unwrap_or(443);
+        let addr = format!("{}:{}", host, port);
         addr
     }
 
-    pub async fn send_request<T: Body + Send + 'static>(&self, body: T) -> Result<Bytes>
+    /// Sends the request and returns the response without reading its body,
+    /// so callers can stream large payloads instead of buffering them.
+    pub async fn open_request<T: Body + Send + 'static>(&self, body: T) -> Result<Response<Incoming>>
     where
         T::Data: Send,
         T::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
@@ -67,7 +70,17 @@ impl Client {
             }
         });
 
-        let mut res = sender.send_request(req).await?;
+        let res = sender.send_request(req).await?;
+
+        Ok(res)
+    }
+
+    pub async fn send_request<T: Body + Send + 'static>(&self, body: T) -> Result<Bytes>
+    where
+        T::Data: Send,
+        T::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let mut res = self.open_request(body).await?;
 
         if res.status().is_client_error() {
             let w = Vec::new();
@@ -121,6 +134,25 @@ impl ClientBuilder {
         Ok(self)
     }
 
+    /// Sets an absolute url, e.g. a `result_url` hosted outside of the D-ID api,
+    /// and points the host header at it.
+    pub fn url(mut self, url: &str) -> Result<Self> {
+        let url = url.parse::<Uri>()?;
+        let Some(authority) = url.authority() else {
+            return Err(Box::new(Error::ClientBuildError(
+                "url must be absolute".to_string(),
+            )));
+        };
+        let host_value = authority.as_str().parse::<HeaderValue>()?;
+        // unwrap() is warranted because self.headers has default headers set with one initial entry
+        self.headers
+            .as_mut()
+            .unwrap()
+            .insert(HeaderName::from_static(HOST), host_value);
+        self.url = Some(url);
+        Ok(self)
+    }
+
     pub fn method(mut self, method: impl Into<String>) -> Result<Self> {
         let method = method.into().parse::<Method>()?;
         self.method = Some(method);
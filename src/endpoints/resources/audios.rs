@@ -2,9 +2,20 @@
 // Path: src/endpoints/resources/audios.rs
 
 use super::*;
+use super::uploads::UploadRegistry;
+use crate::support::audio::{downmix, resample, trim_silence};
+use crate::support::wav::{read_wav_samples, write_mono_pcm16_wav};
+use std::sync::Arc;
+use tokio::io::AsyncRead;
+use tokio::sync::Mutex;
 
 const AUDIOS_PATH: &str = "/audios";
 
+/// Sample rate D-ID stores uploaded audio in.
+pub const NORMALIZED_SAMPLE_RATE: u32 = 16_000;
+/// Samples quieter than this, about -40dBFS, are silence by default.
+pub const DEFAULT_SILENCE_THRESHOLD: f32 = 0.01;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AudioResponse {
     pub id: String,
@@ -13,13 +24,126 @@ pub struct AudioResponse {
 }
 
 /// Upload an audio to a temporary storage before creating an animation.
-/// Supported mime types: audio/, video/,
+/// Supported formats: WAV, MP3, MP4, M4A, OGG and WebM, detected from the content
 /// Storage duration: 24-48H
 /// The resulting file is stored as a .wav file in a 16kHz sample rate.
 /// The maximum file size is 6MB.
-pub async  fn upload_audio_by_file(mime_type: &str, path: &str) -> Result<AudioResponse> {
+pub async fn upload_audio_by_file(path: &str) -> Result<AudioResponse> {
+    upload_audio(UploadSource::file(path)).await
+}
+
+pub async fn upload_audio_by_bytes(filename: &str, bytes: impl Into<Bytes>) -> Result<AudioResponse> {
+    upload_audio(UploadSource::bytes(filename, bytes)).await
+}
+
+/// Uploads exactly `len` bytes read from `reader`.
+pub async fn upload_audio_by_reader<R>(filename: &str, reader: R, len: u64) -> Result<AudioResponse>
+where
+    R: AsyncRead + Send + Unpin + 'static,
+{
+    upload_audio(UploadSource::reader(filename, reader, len)).await
+}
+
+/// Lets D-ID fetch the audio from a remote url.
+pub async fn upload_audio_by_url(url: &str) -> Result<AudioResponse> {
+    upload_audio(UploadSource::url(url)).await
+}
+
+/// Converts PCM WAV audio to the mono 16 bit WAV D-ID stores, locally.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioNormalization {
+    pub sample_rate: u32,
+    /// Leading and trailing samples quieter than this are trimmed, `None`
+    /// keeps them.
+    pub silence_threshold: Option<f32>,
+}
+
+impl Default for AudioNormalization {
+    fn default() -> Self {
+        Self {
+            sample_rate: NORMALIZED_SAMPLE_RATE,
+            silence_threshold: Some(DEFAULT_SILENCE_THRESHOLD),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NormalizedAudio {
+    pub wav: Bytes,
+    /// Length in seconds of the normalized audio.
+    pub duration: f64,
+}
+
+impl AudioNormalization {
+    /// Downmixes, resamples and trims a PCM or float WAV file.
+    /// Fails with `UploadError::ZeroSampleRate` when `sample_rate` is 0, and
+    /// with `UploadError::SilentAudio` when trimming leaves nothing.
+    pub fn apply(&self, wav: &[u8]) -> Result<NormalizedAudio> {
+        if self.sample_rate == 0 {
+            return Err(Box::new(UploadError::ZeroSampleRate));
+        }
+        let (info, channels) = read_wav_samples(wav)?;
+        let mono = downmix(&channels);
+        let resampled = resample(&mono, info.sample_rate, self.sample_rate);
+        let samples = match self.silence_threshold {
+            Some(threshold) => trim_silence(&resampled, threshold),
+            None => &resampled,
+        };
+        if samples.is_empty() {
+            return Err(Box::new(UploadError::SilentAudio));
+        }
+
+        Ok(NormalizedAudio {
+            wav: write_mono_pcm16_wav(self.sample_rate, samples).into(),
+            duration: samples.len() as f64 / self.sample_rate as f64,
+        })
+    }
+}
+
+/// Normalizes WAV audio before it is uploaded, reporting its duration.
+pub async fn normalize_audio(source: UploadSource, normalization: &AudioNormalization) -> Result<NormalizedAudio> {
+    normalization.apply(&source.read_all().await?)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AudioUploadOptions {
+    /// Normalizes WAV audio locally before uploading it, other formats and
+    /// remote urls are uploaded as they are.
+    pub normalize: Option<AudioNormalization>,
+    /// Checked after normalization, remote urls are left for D-ID to check.
+    pub limits: UploadLimits,
+    /// Records the uploaded audio, so a `Janitor` deletes it later.
+    pub registry: Option<Arc<Mutex<UploadRegistry>>>,
+}
+
+#[derive(Debug)]
+pub struct UploadedAudio {
+    pub audio: AudioResponse,
+    /// Length in seconds of the audio as normalized locally, `None` when it
+    /// was uploaded as it was.
+    pub normalized_duration: Option<f64>,
+}
+
+pub async fn upload_audio(source: UploadSource) -> Result<AudioResponse> {
+    Ok(upload_audio_with_options(source, &AudioUploadOptions::default())
+        .await?
+        .audio)
+}
+
+pub async fn upload_audio_with_options(mut source: UploadSource, options: &AudioUploadOptions) -> Result<UploadedAudio> {
+    let mut normalized_duration = None;
+    if let Some(normalization) = &options.normalize {
+        if source.media_type().await? == Some(MediaType::Wav) {
+            let filename = source.filename().unwrap_or("audio.wav").to_string();
+            let normalized = normalize_audio(source, normalization).await?;
+            normalized_duration = Some(normalized.duration);
+            source = UploadSource::bytes(&filename, normalized.wav);
+        }
+    }
+
+    let mime_type = source.validate("audio", MediaType::is_audio, &options.limits).await?;
     let mut form = MultipartFormData::new();
-    form.add_file(mime_type, "audio", path)?;
+    form.add_source(mime_type.unwrap_or_default(), "audio", source)?;
     form.end_body()?;
 
     let c = ClientBuilder::new()?
@@ -27,20 +151,27 @@ pub async  fn upload_audio_by_file(mime_type: &str, path: &str) -> Result<AudioR
         .path(AUDIOS_PATH)?
         .header(ACCEPT, APPLICATION_JSON)?
         .header(CONTENT_TYPE, &format!("{}{}", MULTIPART_FORM_DATA_BOUNDARY, form.boundary))?
+        .header(CONTENT_LENGTH, &form.content_length().to_string())?
         .build()?;
 
-    let resp = c.send_request(Full::<Bytes>::new(form.body.into())).await?;
+    let resp = c.send_request(form.into_body()).await?;
 
-    let json = serde_json::from_slice::<AudioResponse>(&resp.as_ref())?;
+    let json = serde_json::from_slice::<AudioResponse>(resp.as_ref())?;
 
-    Ok(json)
-}
+    if let Some(registry) = &options.registry {
+        registry.lock().await.track_audio(&json)?;
+    }
 
+    Ok(UploadedAudio {
+        audio: json,
+        normalized_duration,
+    })
+}
 
 pub async fn delete_audio(id: &str) -> Result<()> {
     let c = ClientBuilder::new()?
         .method(DELETE)?
-        .path(&format!("{}/{}", AUDIOS_PATH, id))?
+        .path(format!("{}/{}", AUDIOS_PATH, id))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
@@ -49,3 +180,49 @@ pub async fn delete_audio(id: &str) -> Result<()> {
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::support::wav::pcm16_wav;
+
+    #[tokio::test]
+    async fn wav_is_normalized_to_16khz_mono() {
+        // Half a second of silence around a second of a 48kHz stereo tone
+        let mut samples = vec![0i16; 48000];
+        for i in 0..48000 {
+            let tone = (8000.0 * (i as f64 * 0.05).sin()) as i16;
+            samples.push(tone);
+            samples.push(tone);
+        }
+        samples.extend(vec![0i16; 48000]);
+        let wav = pcm16_wav(2, 48000, &samples);
+
+        let normalized = normalize_audio(UploadSource::bytes("tone.wav", wav), &AudioNormalization::default())
+            .await
+            .unwrap();
+
+        assert!((normalized.duration - 1.0).abs() < 0.01, "{}", normalized.duration);
+        let (info, channels) = read_wav_samples(&normalized.wav).unwrap();
+        assert_eq!((info.channels, info.sample_rate), (1, 16000));
+        assert_eq!(channels[0].len() as f64 / 16000.0, normalized.duration);
+    }
+
+    #[test]
+    fn silent_wav_is_rejected() {
+        let wav = pcm16_wav(1, 16000, &[0; 16000]);
+
+        let err = AudioNormalization::default().apply(&wav).unwrap_err();
+
+        assert!(matches!(err.downcast_ref::<UploadError>(), Some(UploadError::SilentAudio)));
+    }
+
+    #[test]
+    fn zero_target_sample_rate_is_rejected() {
+        let wav = pcm16_wav(1, 16000, &[16384; 16000]);
+        let normalization = AudioNormalization { sample_rate: 0, ..Default::default() };
+
+        let err = normalization.apply(&wav).unwrap_err();
+
+        assert!(matches!(err.downcast_ref::<UploadError>(), Some(UploadError::ZeroSampleRate)));
+    }
+}
@@ -2,27 +2,121 @@
 // Path: src/endpoints/resources/credits.rs
 
 use super::*;
-
+use crate::endpoints::video::animations::{AnimationRequestBody, PostAnimationResponse};
+use crate::endpoints::video::clips::{ClipRequestBody, CreateClipResponse};
+use crate::endpoints::video::estimate::{CostEstimate, CostEstimator};
+use crate::endpoints::video::talks::{CreateTalkResponse, TalkRequestBody};
+use crate::error::InsufficientCredits;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
 const CREDITS_PATH: &str = "/credits";
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreditResponse {
-    credits: Vec<Info>,
-    remaining: i64,
-    total: i64,
+    pub credits: Vec<Info>,
+    pub remaining: i64,
+    pub total: i64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A credit pack of the account.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Info {
-    owner_id: String,
-    expire_at: String,
-    created_at: String,
-    remaining: i64,
-    valid_from: String,
-    total: i64,
-    product_id: String,
-    modified_at: String,
+    pub owner_id: String,
+    pub expire_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub remaining: i64,
+    pub valid_from: DateTime<Utc>,
+    pub total: i64,
+    pub product_id: String,
+    pub modified_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProductUsage {
+    pub remaining: i64,
+    pub total: i64,
+    pub used: i64,
+}
+
+impl CreditResponse {
+    /// Sums the credit packs of every product.
+    pub fn by_product(&self) -> BTreeMap<String, ProductUsage> {
+        let mut products: BTreeMap<String, ProductUsage> = BTreeMap::new();
+        for pack in &self.credits {
+            let usage = products.entry(pack.product_id.clone()).or_default();
+            usage.remaining += pack.remaining;
+            usage.total += pack.total;
+            usage.used += pack.total - pack.remaining;
+        }
+        products
+    }
+
+    /// Packs with credits left that have not expired at `now` but will before
+    /// `now + within`, soonest first.
+    pub fn expiring_within(&self, now: DateTime<Utc>, within: TimeDelta) -> Vec<&Info> {
+        let mut packs = self
+            .credits
+            .iter()
+            .filter(|pack| {
+                pack.remaining > 0 && pack.expire_at > now && pack.expire_at <= now + within
+            })
+            .collect::<Vec<_>>();
+        packs.sort_by_key(|pack| pack.expire_at);
+        packs
+    }
+}
+
+/// The credit balance at a point in time.
+#[derive(Debug, Clone)]
+pub struct CreditSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub credits: CreditResponse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BurnRate {
+    /// Credits used between the two snapshots.
+    pub consumed: i64,
+    pub per_day: f64,
+}
+
+impl CreditSnapshot {
+    pub async fn take() -> Result<Self> {
+        Ok(Self {
+            taken_at: Utc::now(),
+            credits: get_credits().await?,
+        })
+    }
+
+    /// Credits used from `earlier` until this snapshot.
+    /// Only packs present in both snapshots count, so top-ups and expired packs
+    /// do not skew the rate.
+    pub fn burn_rate_since(&self, earlier: &CreditSnapshot) -> BurnRate {
+        let consumed = self
+            .credits
+            .credits
+            .iter()
+            .filter_map(|pack| {
+                let before = earlier.credits.credits.iter().find(|before| {
+                    before.product_id == pack.product_id && before.created_at == pack.created_at
+                })?;
+                Some((before.remaining - pack.remaining).max(0))
+            })
+            .sum::<i64>();
+
+        let seconds = (self.taken_at - earlier.taken_at).num_seconds();
+        let per_day = if seconds > 0 {
+            consumed as f64 * 86_400.0 / seconds as f64
+        } else {
+            0.0
+        };
+
+        BurnRate { consumed, per_day }
+    }
 }
 
 pub async fn get_credits() -> Result<CreditResponse> {
@@ -34,7 +128,251 @@ pub async fn get_credits() -> Result<CreditResponse> {
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let credits = serde_json::from_slice::<CreditResponse>(&resp.as_ref())?;
+    let credits = serde_json::from_slice::<CreditResponse>(resp.as_ref())?;
 
     Ok(credits)
 }
+
+/// How often a credit guard reloads the balance from `/credits` by default.
+pub const DEFAULT_CREDIT_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Stops talks, clips and animations from being created once the credit balance
+/// would drop below a reserve.
+/// Jobs are created through the guard, which caches the balance, decrements it
+/// locally by the estimated cost of every job and reloads it from `/credits`
+/// once `refresh_interval` has passed, less the credits of jobs still being
+/// created. A job that fails to be created is refunded, and a job whose cost
+/// cannot be estimated is rejected. Share one guard, e.g. in an `Arc`, between everything that spends
+/// from the same account.
+#[derive(Debug)]
+pub struct CreditGuard {
+    reserve: i64,
+    refresh_interval: Duration,
+    estimator: CostEstimator,
+    balance: Mutex<Balance>,
+}
+
+#[derive(Debug, Default)]
+struct Balance {
+    remaining: Option<i64>,
+    /// Credits taken by jobs whose create call has not returned yet, which
+    /// the balance loaded from the api does not account for.
+    in_flight: i64,
+    refreshed_at: Option<Instant>,
+}
+
+impl Balance {
+    fn is_stale(&self, refresh_interval: Duration) -> bool {
+        self.refreshed_at
+            .is_none_or(|at| at.elapsed() >= refresh_interval)
+    }
+
+    fn take(&mut self, credits: u32, reserve: i64) -> Result<()> {
+        let remaining = self.remaining.unwrap_or_default();
+        if remaining - (credits as i64) < reserve {
+            return Err(Box::new(InsufficientCredits {
+                remaining,
+                required: credits,
+                reserve,
+            }));
+        }
+        self.remaining = Some(remaining - credits as i64);
+        self.in_flight += credits as i64;
+        Ok(())
+    }
+
+    fn refresh(&mut self, remaining: i64) {
+        self.remaining = Some(remaining - self.in_flight);
+        self.refreshed_at = Some(Instant::now());
+    }
+
+    /// Ends the reservation of `credits` taken for a job, refunding them if
+    /// the job was not created.
+    fn settle(&mut self, credits: u32, created: bool) {
+        self.in_flight -= credits as i64;
+        if let (false, Some(remaining)) = (created, self.remaining.as_mut()) {
+            *remaining += credits as i64;
+        }
+    }
+}
+
+impl CreditGuard {
+    pub fn new(reserve: i64) -> Self {
+        Self {
+            reserve,
+            refresh_interval: DEFAULT_CREDIT_REFRESH_INTERVAL,
+            estimator: CostEstimator::new(),
+            balance: Mutex::new(Balance::default()),
+        }
+    }
+
+    pub fn refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    pub fn estimator(mut self, estimator: CostEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// The locally tracked balance, if it has been loaded.
+    pub fn balance(&self) -> Option<i64> {
+        self.lock().remaining
+    }
+
+    pub async fn create_talk(&self, body: &TalkRequestBody) -> Result<CreateTalkResponse> {
+        self.charged(self.estimator.estimate_talk(body), body.create_talk())
+            .await
+    }
+
+    pub async fn create_clip(&self, body: &ClipRequestBody) -> Result<CreateClipResponse> {
+        self.charged(self.estimator.estimate_clip(body), body.create())
+            .await
+    }
+
+    pub async fn create_animation(&self, body: &AnimationRequestBody) -> Result<PostAnimationResponse> {
+        self.charged(self.estimator.estimate_animation(body), body.create_animation())
+            .await
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Balance> {
+        self.balance.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Charges the estimated cost of a job before running `create`, and
+    /// refunds it when `create` fails.
+    /// Jobs whose cost cannot be estimated, such as audio scripts of unknown
+    /// length, fail with the estimate error without being created.
+    async fn charged<T, F>(&self, estimate: Result<CostEstimate>, create: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let credits = estimate?.credits;
+
+        // The balance is not locked while it is reloaded, so other jobs are
+        // not held up by the request.
+        if self.lock().is_stale(self.refresh_interval) {
+            let remaining = get_credits().await?.remaining;
+            self.lock().refresh(remaining);
+        }
+        self.lock().take(credits, self.reserve)?;
+
+        let created = create.await;
+        self.lock().settle(credits, created.is_ok());
+        created
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::EstimateError;
+    use crate::support::fixtures::credits;
+
+    #[test]
+    fn credits_are_aggregated_by_product() {
+        let resp = credits(&[
+            ("prod_a", "2023-10-01T00:00:00.000Z", "2023-11-01T00:00:00.000Z", 5, 20),
+            ("prod_a", "2023-10-05T00:00:00.000Z", "2023-11-05T00:00:00.000Z", 10, 10),
+            ("prod_b", "2023-10-01T00:00:00.000Z", "2024-10-01T00:00:00.000Z", 100, 100),
+        ]);
+
+        let products = resp.by_product();
+
+        assert_eq!(products["prod_a"], ProductUsage { remaining: 15, total: 30, used: 15 });
+        assert_eq!(products["prod_b"], ProductUsage { remaining: 100, total: 100, used: 0 });
+    }
+
+    #[test]
+    fn expiring_packs_are_found() {
+        let resp = credits(&[
+            ("prod_b", "2023-10-01T00:00:00Z", "2024-10-01T00:00:00Z", 100, 100),
+            ("prod_a", "2023-10-05T00:00:00Z", "2023-11-05T00:00:00Z", 10, 10),
+            ("prod_a", "2023-10-01T00:00:00Z", "2023-11-01T00:00:00Z", 5, 20),
+            ("prod_c", "2023-10-01T00:00:00Z", "2023-10-20T00:00:00Z", 0, 20),
+            ("prod_d", "2023-09-01T00:00:00Z", "2023-10-10T00:00:00Z", 7, 20),
+        ]);
+        let now = "2023-10-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let expiring = resp.expiring_within(now, TimeDelta::days(30));
+
+        assert_eq!(expiring.len(), 2);
+        assert_eq!(expiring[0].remaining, 5);
+        assert_eq!(expiring[1].remaining, 10);
+    }
+
+    #[test]
+    fn burn_rate_ignores_top_ups() {
+        let earlier = CreditSnapshot {
+            taken_at: "2023-10-01T00:00:00Z".parse().unwrap(),
+            credits: credits(&[("prod_a", "2023-09-01T00:00:00Z", "2023-12-01T00:00:00Z", 50, 100)]),
+        };
+        let later = CreditSnapshot {
+            taken_at: "2023-10-03T00:00:00Z".parse().unwrap(),
+            credits: credits(&[
+                ("prod_a", "2023-09-01T00:00:00Z", "2023-12-01T00:00:00Z", 30, 100),
+                ("prod_a", "2023-10-02T00:00:00Z", "2024-01-01T00:00:00Z", 100, 100),
+            ]),
+        };
+
+        let rate = later.burn_rate_since(&earlier);
+
+        assert_eq!(rate, BurnRate { consumed: 20, per_day: 10.0 });
+    }
+
+    #[test]
+    fn credit_guard_stops_at_the_reserve() {
+        let mut balance = Balance::default();
+        balance.refresh(20);
+
+        assert!(!balance.is_stale(DEFAULT_CREDIT_REFRESH_INTERVAL));
+        assert!(balance.take(6, 10).is_ok());
+        assert_eq!(balance.remaining, Some(14));
+
+        let err = balance.take(5, 10).unwrap_err();
+        let err = err.downcast_ref::<InsufficientCredits>().unwrap();
+        assert_eq!(err.remaining, 14);
+        assert_eq!(err.required, 5);
+        assert_eq!(balance.remaining, Some(14));
+    }
+
+    #[test]
+    fn refresh_keeps_credits_of_jobs_in_flight() {
+        let mut balance = Balance::default();
+        balance.refresh(20);
+        balance.take(6, 0).unwrap();
+
+        balance.refresh(20);
+        assert_eq!(balance.remaining, Some(14));
+
+        balance.settle(6, true);
+        balance.refresh(14);
+        assert_eq!(balance.remaining, Some(14));
+    }
+
+    #[tokio::test]
+    async fn failed_jobs_are_refunded() {
+        let guard = CreditGuard::new(0);
+        guard.lock().refresh(10);
+
+        let created = guard
+            .charged(Ok(CostEstimate { duration: None, credits: 4 }), async { Ok(()) })
+            .await;
+        assert!(created.is_ok());
+        assert_eq!(guard.balance(), Some(6));
+
+        let failed = guard
+            .charged::<(), _>(Ok(CostEstimate { duration: None, credits: 4 }), async {
+                Err("rejected".into())
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(guard.balance(), Some(6));
+
+        let unknown_duration = EstimateError::UnknownAudioDuration("a.wav".to_string());
+        let unknown = guard.charged(Err(unknown_duration.into()), async { Ok(()) }).await;
+        assert!(unknown.is_err());
+        assert_eq!(guard.balance(), Some(6));
+    }
+}
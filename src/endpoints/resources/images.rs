@@ -1,25 +1,223 @@
 // File: images.rs
-// Path: src/endpoints/resources/voices.rs
+// Path: src/endpoints/resources/images.rs
 
 use super::*;
+use super::uploads::UploadRegistry;
+use crate::endpoints::video::{Detection, Face};
+use image::codecs::{jpeg::JpegEncoder, png::PngEncoder};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use std::sync::Arc;
+use tokio::io::AsyncRead;
+use tokio::sync::Mutex;
 
 const IMAGES_PATH: &str = "/images";
 
+/// Longest side of preprocessed images by default.
+pub const DEFAULT_MAX_IMAGE_DIMENSION: u32 = 2048;
+pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
 // This temporary URL should be provided when creating an animation via the /animations endpoint.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ImageResponse {
     pub id: String,
     pub url: String,
+    /// Faces found in the image when uploaded with `detect_faces`.
+    #[serde(default)]
+    pub faces: Vec<Face>,
+}
+
+impl ImageResponse {
+    /// The face covering the largest area of the image.
+    pub fn largest_face(&self) -> Option<&Face> {
+        self.faces
+            .iter()
+            .max_by(|a, b| a.detection.area().total_cmp(&b.detection.area()))
+    }
+
+    /// The face detected with the highest confidence.
+    pub fn most_confident_face(&self) -> Option<&Face> {
+        self.faces
+            .iter()
+            .max_by(|a, b| a.detection_confidence.total_cmp(&b.detection_confidence))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImageUploadOptions {
+    /// Detect faces on upload and return them in `ImageResponse::faces`, ready
+    /// for `AnimationRequestBodyBuilder::face` or `NewStreamRequestBodyBuilder::face`.
+    pub detect_faces: bool,
+    /// Processes local images before uploading them, remote urls are uploaded
+    /// as they are.
+    pub preprocess: Option<ImagePreprocessing>,
+    /// Checked after preprocessing, remote urls are left for D-ID to check.
+    pub limits: UploadLimits,
+    /// Records the uploaded image, so a `Janitor` deletes it later.
+    pub registry: Option<Arc<Mutex<UploadRegistry>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageOutputFormat {
+    Jpeg { quality: u8 },
+    Png,
+}
+
+/// Prepares a photo for upload, locally.
+/// Steps run in order: EXIF orientation, crop, downscale, encoding.
+#[derive(Debug, Clone)]
+pub struct ImagePreprocessing {
+    /// Rotates and flips the pixels as the EXIF orientation says, so the
+    /// image is not rendered sideways.
+    pub auto_orient: bool,
+    /// Longest side after downscaling, the aspect ratio is kept and smaller
+    /// images are left as they are.
+    pub max_dimension: Option<u32>,
+    pub format: ImageOutputFormat,
+    /// Crops around a face box, in pixels of the oriented image.
+    pub crop: Option<Detection>,
+    /// Space kept around the crop box on each side, as a fraction of its size.
+    pub crop_margin: f32,
+}
+
+impl Default for ImagePreprocessing {
+    fn default() -> Self {
+        Self {
+            auto_orient: true,
+            max_dimension: Some(DEFAULT_MAX_IMAGE_DIMENSION),
+            format: ImageOutputFormat::Jpeg { quality: DEFAULT_JPEG_QUALITY },
+            crop: None,
+            crop_margin: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessedImage {
+    pub bytes: Bytes,
+    pub media_type: MediaType,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ImagePreprocessing {
+    pub fn apply(&self, image: &[u8]) -> Result<ProcessedImage> {
+        let mut decoder = ImageReader::new(io::Cursor::new(image))
+            .with_guessed_format()?
+            .into_decoder()?;
+        let orientation = decoder.orientation()?;
+        let mut image = DynamicImage::from_decoder(decoder)?;
+
+        if self.auto_orient {
+            image.apply_orientation(orientation);
+        }
+        if let Some(detection) = &self.crop {
+            image = self.crop_around(&image, detection)?;
+        }
+        if let Some(max) = self.max_dimension {
+            if image.width() > max || image.height() > max {
+                image = image.resize(max, max, FilterType::Lanczos3);
+            }
+        }
+
+        let mut bytes = Vec::new();
+        let media_type = match self.format {
+            ImageOutputFormat::Jpeg { quality } => {
+                // JPEG has no alpha channel
+                DynamicImage::ImageRgb8(image.to_rgb8())
+                    .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, quality))?;
+                MediaType::Jpeg
+            }
+            ImageOutputFormat::Png => {
+                image.write_with_encoder(PngEncoder::new(&mut bytes))?;
+                MediaType::Png
+            }
+        };
+
+        Ok(ProcessedImage {
+            bytes: bytes.into(),
+            media_type,
+            width: image.width(),
+            height: image.height(),
+        })
+    }
+
+    fn crop_around(&self, image: &DynamicImage, detection: &Detection) -> Result<DynamicImage> {
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        let margin_x = detection.width() * self.crop_margin;
+        let margin_y = detection.height() * self.crop_margin;
+        let left = (detection.left - margin_x).clamp(0.0, width);
+        let top = (detection.top - margin_y).clamp(0.0, height);
+        let right = (detection.right + margin_x).clamp(0.0, width);
+        let bottom = (detection.bottom + margin_y).clamp(0.0, height);
+
+        if right - left < 1.0 || bottom - top < 1.0 {
+            return Err(Box::new(UploadError::CropOutOfBounds {
+                width: image.width(),
+                height: image.height(),
+            }));
+        }
+
+        Ok(image.crop_imm(
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        ))
+    }
+}
+
+/// Preprocesses a local image, for inspection before it is uploaded.
+pub async fn preprocess_image(source: UploadSource, preprocessing: &ImagePreprocessing) -> Result<ProcessedImage> {
+    preprocessing.apply(&source.read_all().await?)
 }
 
 /// Upload an image to a temporary storage before creating an animation.
 /// Supported mime types: image/jpeg,image/png
 /// Storage duration: 24-48H
-// TODO: Cover all body params [https://docs.d-id.com/reference/upload-an-image]
 pub async fn upload_image_by_file(path: &str) -> Result<ImageResponse> {
-    let mime_subtype = path.split(".").last().ok_or("Invalid file path")?;
+    upload_image(UploadSource::file(path), &ImageUploadOptions::default()).await
+}
+
+pub async fn upload_image_by_file_with_options(path: &str, options: &ImageUploadOptions) -> Result<ImageResponse> {
+    upload_image(UploadSource::file(path), options).await
+}
+
+pub async fn upload_image_by_bytes(filename: &str, bytes: impl Into<Bytes>) -> Result<ImageResponse> {
+    upload_image(UploadSource::bytes(filename, bytes), &ImageUploadOptions::default()).await
+}
+
+/// Uploads exactly `len` bytes read from `reader`.
+pub async fn upload_image_by_reader<R>(filename: &str, reader: R, len: u64) -> Result<ImageResponse>
+where
+    R: AsyncRead + Send + Unpin + 'static,
+{
+    upload_image(UploadSource::reader(filename, reader, len), &ImageUploadOptions::default()).await
+}
+
+/// Lets D-ID fetch the image from a remote url.
+pub async fn upload_image_by_url(url: &str) -> Result<ImageResponse> {
+    upload_image(UploadSource::url(url), &ImageUploadOptions::default()).await
+}
+
+/// The image type is detected from its content, only JPEG and PNG are accepted.
+pub async fn upload_image(mut source: UploadSource, options: &ImageUploadOptions) -> Result<ImageResponse> {
+    if let (Some(preprocessing), Some(filename)) = (&options.preprocess, source.filename()) {
+        let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem).to_string();
+        let processed = preprocess_image(source, preprocessing).await?;
+        let extension = match processed.media_type {
+            MediaType::Png => "png",
+            _ => "jpg",
+        };
+        source = UploadSource::bytes(&format!("{}.{}", stem, extension), processed.bytes);
+    }
+
+    let mime_type = source.validate("image", MediaType::is_image, &options.limits).await?;
     let mut form = MultipartFormData::new();
-    form.add_file(&format!("image/{}", mime_subtype), "image", path)?;
+    form.add_source(mime_type.unwrap_or_default(), "image", source)?;
+    if options.detect_faces {
+        form.add_text("detect_faces", "true")?;
+    }
     form.end_body()?;
 
     let c = ClientBuilder::new()?
@@ -27,11 +225,16 @@ pub async fn upload_image_by_file(path: &str) -> Result<ImageResponse> {
         .path(IMAGES_PATH)?
         .header(ACCEPT, APPLICATION_JSON)?
         .header(CONTENT_TYPE, &format!("{}{}", MULTIPART_FORM_DATA_BOUNDARY, form.boundary))?
+        .header(CONTENT_LENGTH, &form.content_length().to_string())?
         .build()?;
 
-    let resp = c.send_request(Full::<Bytes>::new(form.body.into())).await?;
+    let resp = c.send_request(form.into_body()).await?;
+
+    let json = serde_json::from_slice::<ImageResponse>(resp.as_ref())?;
 
-    let json = serde_json::from_slice::<ImageResponse>(&resp.as_ref())?;
+    if let Some(registry) = &options.registry {
+        registry.lock().await.track_image(&json)?;
+    }
 
     Ok(json)
 }
@@ -39,7 +242,7 @@ pub async fn upload_image_by_file(path: &str) -> Result<ImageResponse> {
 pub async fn delete_image(id: &str) -> Result<()> {
     let c = ClientBuilder::new()?
         .method(DELETE)?
-        .path(&format!("{}/{}", IMAGES_PATH, id))?
+        .path(format!("{}/{}", IMAGES_PATH, id))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
@@ -48,3 +251,93 @@ pub async fn delete_image(id: &str) -> Result<()> {
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = io::Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut bytes, ImageFormat::Jpeg)
+            .unwrap();
+        bytes.into_inner()
+    }
+
+    /// Inserts an APP1 segment with an EXIF orientation tag after the SOI marker.
+    fn with_orientation(jpeg: &[u8], orientation: u8) -> Vec<u8> {
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0".to_vec();
+        exif.extend_from_slice(&[orientation, 0, 0, 0, 0, 0, 0]);
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+        out.extend_from_slice(&exif);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    #[test]
+    fn images_are_oriented_and_downscaled() {
+        let preprocessing = ImagePreprocessing {
+            max_dimension: Some(20),
+            format: ImageOutputFormat::Png,
+            ..Default::default()
+        };
+
+        // Orientation 6 is a 90 degree clockwise rotation
+        let got = preprocessing.apply(&with_orientation(&jpeg(80, 40), 6)).unwrap();
+
+        assert_eq!((got.width, got.height), (10, 20));
+        assert_eq!(got.media_type, MediaType::Png);
+        assert_eq!(crate::support::sniff::sniff(&got.bytes), Some(MediaType::Png));
+    }
+
+    #[test]
+    fn images_are_cropped_around_a_face() {
+        let face = Detection { top: 40.0, left: 20.0, bottom: 60.0, right: 40.0 };
+        let preprocessing = ImagePreprocessing {
+            crop: Some(face),
+            ..Default::default()
+        };
+
+        let got = preprocessing.apply(&jpeg(100, 100)).unwrap();
+        assert_eq!((got.width, got.height), (40, 40));
+
+        let outside = ImagePreprocessing {
+            crop: Some(Detection { top: 200.0, left: 200.0, bottom: 210.0, right: 210.0 }),
+            ..Default::default()
+        };
+        assert!(outside.apply(&jpeg(100, 100)).is_err());
+    }
+
+    #[test]
+    fn detected_faces_are_selectable() {
+        let resp = serde_json::from_value::<ImageResponse>(serde_json::json!({
+            "id": "img_1",
+            "url": "s3://d-id-images-prod/img_1.jpg",
+            "faces": [
+                {
+                    "size": 256,
+                    "top_left": [10, 10],
+                    "overlap": "NO",
+                    "face_id": "small",
+                    "detect_confidence": 0.99,
+                    "detection": {"top": 10.0, "left": 10.0, "bottom": 110.0, "right": 110.0},
+                },
+                {
+                    "size": 512,
+                    "top_left": [200, 50],
+                    "overlap": "PARTIAL",
+                    "face_id": "large",
+                    "detection_confidence": 0.8,
+                    "detection": {"top": 50.0, "left": 200.0, "bottom": 450.0, "right": 500.0},
+                },
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(resp.largest_face().unwrap().face_id, "large");
+        assert_eq!(resp.most_confident_face().unwrap().face_id, "small");
+    }
+}
@@ -1,19 +1,288 @@
 pub mod credits;
 pub mod voices;
+pub mod voice_resolver;
 pub mod images;
 pub mod audios;
 pub mod settings;
+pub mod uploads;
 
-use std::{fs::File, io::{self, Read, Write}};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
 pub use crate::client::*;
 pub use crate::prelude::*;
 pub use serde::{Deserialize, Serialize};
 
 use rand::Rng;
+use http_body::{Frame, SizeHint};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use crate::support::probe::{audio_duration, image_dimensions};
+use crate::support::sniff::{sniff, SNIFF_LEN};
+pub use crate::error::UploadError;
+pub use crate::support::sniff::MediaType;
 
+/// Where the content of an uploaded image or audio comes from.
+pub enum UploadSource {
+    /// A local file.
+    File(String),
+    /// Content already in memory.
+    Bytes { filename: String, bytes: Bytes },
+    /// Exactly `len` bytes read from `reader`.
+    Reader {
+        filename: String,
+        reader: Box<dyn AsyncRead + Send + Unpin>,
+        len: u64,
+    },
+    /// A remote file fetched by D-ID itself.
+    Url(String),
+}
+
+impl UploadSource {
+    pub fn file(path: &str) -> Self {
+        Self::File(path.to_string())
+    }
+
+    pub fn bytes(filename: &str, bytes: impl Into<Bytes>) -> Self {
+        Self::Bytes {
+            filename: filename.to_string(),
+            bytes: bytes.into(),
+        }
+    }
+
+    pub fn reader<R: AsyncRead + Send + Unpin + 'static>(filename: &str, reader: R, len: u64) -> Self {
+        Self::Reader {
+            filename: filename.to_string(),
+            reader: Box::new(reader),
+            len,
+        }
+    }
+
+    pub fn url(url: &str) -> Self {
+        Self::Url(url.to_string())
+    }
+
+    /// Name of the uploaded file, not known for remote urls.
+    pub fn filename(&self) -> Option<&str> {
+        match self {
+            Self::File(path) => Some(path),
+            Self::Bytes { filename, .. } | Self::Reader { filename, .. } => Some(filename),
+            Self::Url(_) => None,
+        }
+    }
+
+    /// Size of the content in bytes, not known for remote urls.
+    pub async fn len(&self) -> io::Result<Option<u64>> {
+        match self {
+            Self::File(path) => Ok(Some(tokio::fs::metadata(path).await?.len())),
+            Self::Bytes { bytes, .. } => Ok(Some(bytes.len() as u64)),
+            Self::Reader { len, .. } => Ok(Some(*len)),
+            Self::Url(_) => Ok(None),
+        }
+    }
+
+    /// Reads the whole content into memory, for processing before upload.
+    pub async fn read_all(self) -> Result<Bytes> {
+        match self {
+            Self::File(path) => Ok(tokio::fs::read(path).await?.into()),
+            Self::Bytes { bytes, .. } => Ok(bytes),
+            Self::Reader { reader, len, .. } => {
+                // Grown as the reader yields, so a wrong `len` cannot
+                // reserve memory up front
+                let mut content = Vec::new();
+                reader.take(len).read_to_end(&mut content).await?;
+                Ok(content.into())
+            }
+            Self::Url(_) => Err(Box::new(UploadError::RemoteSource)),
+        }
+    }
+
+    /// Up to `max` bytes from the start of the content, without consuming
+    /// readers. Empty for remote urls.
+    async fn head(&mut self, max: usize) -> io::Result<Vec<u8>> {
+        let mut head = Vec::with_capacity(max);
+        match self {
+            Self::File(path) => {
+                let file = tokio::fs::File::open(path).await?;
+                file.take(max as u64).read_to_end(&mut head).await?;
+            }
+            Self::Bytes { bytes, .. } => head.extend_from_slice(&bytes[..bytes.len().min(max)]),
+            Self::Reader { reader, .. } => {
+                reader.take(max as u64).read_to_end(&mut head).await?;
+                // Put the bytes read back in front of the rest of the reader
+                let rest = std::mem::replace(reader, Box::new(tokio::io::empty()));
+                *reader = Box::new(io::Cursor::new(head.clone()).chain(rest));
+            }
+            Self::Url(_) => {}
+        }
+        Ok(head)
+    }
+
+    /// Detects the format of the content from its first bytes, without
+    /// consuming readers. Remote urls are not inspected and give `None`.
+    pub async fn media_type(&mut self) -> Result<Option<MediaType>> {
+        if let Self::Url(_) = self {
+            return Ok(None);
+        }
+        match sniff(&self.head(SNIFF_LEN).await?) {
+            Some(media_type) => Ok(Some(media_type)),
+            None => Err(Box::new(UploadError::UnknownMediaType)),
+        }
+    }
+
+    /// Checks the content before it is uploaded and returns the MIME type to
+    /// send it as. Fails unless its format is accepted for `expected` uploads
+    /// and it is within `limits`.
+    /// Remote urls are left for D-ID to check and give `None`.
+    pub(crate) async fn validate(
+        &mut self,
+        expected: &'static str,
+        accepts: fn(&MediaType) -> bool,
+        limits: &UploadLimits,
+    ) -> Result<Option<&'static str>> {
+        let Some(len) = self.len().await? else {
+            return Ok(None);
+        };
+        let head = self.head(PROBE_LEN).await?;
+        let media_type = sniff(&head).ok_or(UploadError::UnknownMediaType)?;
+        if !accepts(&media_type) {
+            return Err(Box::new(UploadError::UnsupportedMediaType {
+                expected,
+                mime_type: media_type.mime_type(),
+            }));
+        }
+        limits.check(media_type, len, &head)?;
+
+        Ok(Some(media_type.mime_type()))
+    }
+}
+
+/// Bytes read from the start of an upload to find its dimensions or duration.
+const PROBE_LEN: usize = 256 * 1024;
+
+/// Limits checked before anything is uploaded, so oversize files fail fast
+/// instead of after a full upload. Passed with the options of every upload,
+/// raise them when D-ID changes its limits.
+/// Image dimensions and audio durations are read from the file headers and
+/// only checked when they can be found there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UploadLimits {
+    pub max_image_size: u64,
+    pub max_image_width: u32,
+    pub max_image_height: u32,
+    pub max_audio_size: u64,
+    /// Longest audio in seconds.
+    pub max_audio_duration: f64,
+}
+
+impl Default for UploadLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl UploadLimits {
+    /// 6MB audio is D-ID's documented limit for `/audios`, see
+    /// <https://docs.d-id.com/reference/upload-an-audio>.
+    /// D-ID documents no limits for the size or dimensions of images or the
+    /// length of audio; 10MB, 4096x4096 and 5 minutes are assumptions that
+    /// stay clear of what the api has been seen to accept.
+    pub const DEFAULT: Self = Self {
+        max_image_size: 10 * 1024 * 1024,
+        max_image_width: 4096,
+        max_image_height: 4096,
+        max_audio_size: 6 * 1024 * 1024,
+        max_audio_duration: 300.0,
+    };
+
+    /// Checks a file of `len` bytes starting with `head`.
+    pub fn check(&self, media_type: MediaType, len: u64, head: &[u8]) -> std::result::Result<(), UploadError> {
+        let max_size = if media_type.is_image() {
+            self.max_image_size
+        } else {
+            self.max_audio_size
+        };
+        if len > max_size {
+            return Err(UploadError::TooLarge { size: len, max: max_size });
+        }
+
+        if let Some((width, height)) = image_dimensions(media_type, head) {
+            if width > self.max_image_width || height > self.max_image_height {
+                return Err(UploadError::ImageTooLarge {
+                    width,
+                    height,
+                    max_width: self.max_image_width,
+                    max_height: self.max_image_height,
+                });
+            }
+        }
+
+        if let Some(duration) = audio_duration(media_type, head, len) {
+            if duration > self.max_audio_duration {
+                return Err(UploadError::AudioTooLong {
+                    duration,
+                    max: self.max_audio_duration,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for UploadSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Bytes { filename, bytes } => f
+                .debug_struct("Bytes")
+                .field("filename", filename)
+                .field("len", &bytes.len())
+                .finish(),
+            Self::Reader { filename, len, .. } => f
+                .debug_struct("Reader")
+                .field("filename", filename)
+                .field("len", len)
+                .finish(),
+            Self::Url(url) => f.debug_tuple("Url").field(url).finish(),
+        }
+    }
+}
+
+/// Size of the chunks files and readers are streamed in.
+const MULTIPART_CHUNK_SIZE: usize = 64 * 1024;
+
+enum Part {
+    Bytes(Bytes),
+    Stream {
+        reader: Box<dyn AsyncRead + Send + Unpin>,
+        len: u64,
+    },
+}
+
+impl Part {
+    fn len(&self) -> u64 {
+        match self {
+            Part::Bytes(bytes) => bytes.len() as u64,
+            Part::Stream { len, .. } => *len,
+        }
+    }
+}
+
+/// A multipart/form-data body whose files are streamed from disk or readers
+/// when it is sent, so its size is known before anything is read.
 pub struct MultipartFormData {
     pub boundary: String,
-    pub body: Vec<u8>,
+    parts: Vec<Part>,
+}
+
+impl Default for MultipartFormData {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MultipartFormData {
@@ -21,34 +290,287 @@ impl MultipartFormData {
         Self {
             boundary: format!(
                 "-----------------------------{}", rand::thread_rng().gen::<u64>()),
-            body: Vec::new(),
+            parts: Vec::new(),
         }
     }
 
+    fn push_bytes(&mut self, bytes: impl Into<Bytes>) {
+        self.parts.push(Part::Bytes(bytes.into()));
+    }
+
+    fn push_file_header(&mut self, mime_type: &str, name: &str, filename: &str) {
+        self.push_bytes(format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            self.boundary,
+            escape_param(name),
+            escape_param(basename(filename)),
+            mime_type,
+        ));
+    }
+
     pub fn add_text(&mut self, name: &str, value: &str) -> io::Result<()> {
-        write!(self.body, "--{}\r\n", self.boundary)?;
-        write!(self.body, "Content-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", name, value)?;
+        self.push_bytes(format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+            self.boundary,
+            escape_param(name),
+            value,
+        ));
         Ok(())
     }
 
+    /// Adds a local file, sent under its file name only.
+    /// The file is opened now but read while the body is sent.
     pub fn add_file(&mut self, mime_type: &str, name: &str, path: &str) -> io::Result<()> {
         if !path.contains(".") {
-            return Err(io::Error::new(io::ErrorKind::Other, "Invalid file path"));
+            return Err(io::Error::other("Invalid file path"));
         }
-        write!(self.body, "--{}\r\n", self.boundary)?;
-        write!(self.body, "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n", name, path)?;
-        write!(self.body, "Content-Type: {}\r\n\r\n", mime_type)?;
-        let mut file = File::open(path)?;
-        file.read_to_end(&mut self.body)?;
-        write!(self.body, "\r\n")?;
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        self.add_reader(mime_type, name, path, tokio::fs::File::from_std(file), len)
+    }
 
+    pub fn add_bytes(&mut self, mime_type: &str, name: &str, filename: &str, content: impl Into<Bytes>) -> io::Result<()> {
+        self.push_file_header(mime_type, name, filename);
+        self.push_bytes(content);
+        self.push_bytes("\r\n");
+        Ok(())
+    }
 
+    /// Adds exactly `len` bytes read from `reader` while the body is sent.
+    /// Sending fails if the reader ends early.
+    pub fn add_reader<R: AsyncRead + Send + Unpin + 'static>(
+        &mut self,
+        mime_type: &str,
+        name: &str,
+        filename: &str,
+        reader: R,
+        len: u64,
+    ) -> io::Result<()> {
+        self.push_file_header(mime_type, name, filename);
+        self.parts.push(Part::Stream {
+            reader: Box::new(reader.take(len)),
+            len,
+        });
+        self.push_bytes("\r\n");
         Ok(())
     }
 
+    /// Adds the content of `source` as the file field `name`, or as the `url`
+    /// field for remote files.
+    pub fn add_source(&mut self, mime_type: &str, name: &str, source: UploadSource) -> io::Result<()> {
+        match source {
+            UploadSource::File(path) => self.add_file(mime_type, name, &path),
+            UploadSource::Bytes { filename, bytes } => self.add_bytes(mime_type, name, &filename, bytes),
+            UploadSource::Reader { filename, reader, len } => {
+                self.add_reader(mime_type, name, &filename, reader, len)
+            }
+            UploadSource::Url(url) => self.add_text("url", &url),
+        }
+    }
+
     pub fn end_body(&mut self) -> io::Result<()> {
-        write!(self.body, "--{}--\r\n", self.boundary)?;
+        self.push_bytes(format!("--{}--\r\n", self.boundary));
         Ok(())
     }
 
+    /// Length in bytes of the encoded body.
+    pub fn content_length(&self) -> u64 {
+        self.parts.iter().map(Part::len).sum()
+    }
+
+    pub fn into_body(self) -> MultipartBody {
+        MultipartBody {
+            remaining: self.content_length(),
+            parts: self.parts.into(),
+            chunk: vec![0; MULTIPART_CHUNK_SIZE].into_boxed_slice(),
+        }
+    }
+}
+
+/// Escapes a Content-Disposition parameter the way browsers encode form data.
+fn escape_param(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// The last component of `path`, so local directory names are never sent.
+fn basename(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// The streaming `Body` of a `MultipartFormData`.
+pub struct MultipartBody {
+    parts: VecDeque<Part>,
+    remaining: u64,
+    chunk: Box<[u8]>,
+}
+
+impl Body for MultipartBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<io::Result<Frame<Bytes>>>> {
+        let this = self.get_mut();
+        loop {
+            let data = match this.parts.front_mut() {
+                None => return Poll::Ready(None),
+                Some(Part::Bytes(bytes)) => std::mem::take(bytes),
+                Some(Part::Stream { len: 0, .. }) => Bytes::new(),
+                Some(Part::Stream { reader, len }) => {
+                    let max = this.chunk.len().min(*len as usize);
+                    let mut buf = ReadBuf::new(&mut this.chunk[..max]);
+                    ready!(Pin::new(reader).poll_read(cx, &mut buf))?;
+                    let read = buf.filled().len();
+                    if read == 0 {
+                        return Poll::Ready(Some(Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            format!("upload ended {} bytes short", len),
+                        ))));
+                    }
+                    *len -= read as u64;
+                    this.remaining -= read as u64;
+                    return Poll::Ready(Some(Ok(Frame::data(Bytes::copy_from_slice(buf.filled())))));
+                }
+            };
+
+            this.parts.pop_front();
+            if !data.is_empty() {
+                this.remaining -= data.len() as u64;
+                return Poll::Ready(Some(Ok(Frame::data(data))));
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.remaining == 0
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form() -> MultipartFormData {
+        let mut form = MultipartFormData::new();
+        form.boundary = "boundary".to_string();
+        form
+    }
+
+    async fn encode(form: MultipartFormData) -> io::Result<String> {
+        let expected = form.content_length();
+        let body = form.into_body().collect().await?.to_bytes();
+        assert_eq!(body.len() as u64, expected);
+        Ok(String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn sources_share_one_encoding() {
+        let mut from_bytes = form();
+        from_bytes
+            .add_source("image/png", "image", UploadSource::bytes("face.png", &b"png"[..]))
+            .unwrap();
+
+        let mut from_reader = form();
+        from_reader
+            .add_source("image/png", "image", UploadSource::reader("face.png", &b"png and more"[..], 3))
+            .unwrap();
+
+        let from_bytes = encode(from_bytes).await.unwrap();
+        assert_eq!(from_bytes, encode(from_reader).await.unwrap());
+        assert_eq!(
+            from_bytes,
+            "--boundary\r\nContent-Disposition: form-data; name=\"image\"; filename=\"face.png\"\r\nContent-Type: image/png\r\n\r\npng\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn sniffing_keeps_the_reader_intact() {
+        let png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+        let mut source = UploadSource::reader("photo.jpg", &png[..], png.len() as u64);
+
+        assert_eq!(source.media_type().await.unwrap(), Some(MediaType::Png));
+        assert!(source
+            .validate("audio", MediaType::is_audio, &UploadLimits::DEFAULT)
+            .await
+            .is_err());
+
+        let mut form = form();
+        form.add_source("image/png", "image", source).unwrap();
+        let body = form.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.windows(png.len()).any(|w| w == png));
+    }
+
+    #[test]
+    fn limits_are_checked() {
+        let limits = UploadLimits {
+            max_image_width: 1024,
+            max_audio_duration: 1.5,
+            ..UploadLimits::DEFAULT
+        };
+        let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 0x07, 0x80, 0, 0, 0x04, 0x38]);
+        let wav = crate::support::wav::pcm16_wav(1, 16000, &[0; 32000]);
+
+        assert!(matches!(
+            limits.check(MediaType::Png, 1000, &png),
+            Err(UploadError::ImageTooLarge { width: 1920, height: 1080, .. })
+        ));
+        assert!(matches!(
+            limits.check(MediaType::Png, 11 * 1024 * 1024, &png[..8]),
+            Err(UploadError::TooLarge { .. })
+        ));
+        assert!(matches!(
+            limits.check(MediaType::Wav, wav.len() as u64, &wav),
+            Err(UploadError::AudioTooLong { duration, .. }) if duration == 2.0
+        ));
+        assert!(UploadLimits::DEFAULT.check(MediaType::Wav, wav.len() as u64, &wav).is_ok());
+    }
+
+    #[tokio::test]
+    async fn short_reader_and_url_source() {
+        let mut short = form();
+        short
+            .add_source("audio/wav", "audio", UploadSource::reader("a.wav", &b"ab"[..], 3))
+            .unwrap();
+        assert!(encode(short).await.is_err());
+
+        let mut form = form();
+        form.add_source("audio/wav", "audio", UploadSource::url("https://example.com/a.wav"))
+            .unwrap();
+
+        assert_eq!(
+            encode(form).await.unwrap(),
+            "--boundary\r\nContent-Disposition: form-data; name=\"url\"\r\n\r\nhttps://example.com/a.wav\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn files_are_streamed_under_their_basename() {
+        let path = std::env::temp_dir().join(format!("d-id \"multipart\" {}.wav", std::process::id()));
+        let content = vec![7u8; MULTIPART_CHUNK_SIZE * 2 + 5];
+        std::fs::write(&path, &content).unwrap();
+
+        let mut form = form();
+        form.add_file("audio/wav", "audio", path.to_str().unwrap()).unwrap();
+        form.end_body().unwrap();
+        let body = encode(form).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let header = format!(
+            "--boundary\r\nContent-Disposition: form-data; name=\"audio\"; filename=\"d-id %22multipart%22 {}.wav\"\r\n",
+            std::process::id()
+        );
+        assert!(body.starts_with(&header));
+        assert!(body.ends_with("\r\n--boundary--\r\n"));
+        assert_eq!(body.matches('\u{7}').count(), content.len());
+    }
 }
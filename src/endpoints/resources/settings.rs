@@ -0,0 +1,88 @@
+// File: settings.rs
+// Path: src/endpoints/resources/settings.rs
+
+use super::*;
+use crate::endpoints::video::{Config, ConfigBuilder, Logo, ResultFormat, TTSProvider};
+use serde_json::{Map, Value};
+
+const SETTINGS_PATH: &str = "/settings";
+
+/// Account-level defaults. Every field is optional.
+///
+/// Unverified: the api reference does not document the `/settings` schema.
+/// The fields modeled here mirror the talk and clip fields they default, and
+/// anything else the api returns is kept in `other`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Settings {
+    /// Webhook notified when talks and clips of the account finish.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+    /// Voice of text scripts that do not choose one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tts: Option<TTSProvider>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo: Option<Logo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_format: Option<ResultFormat>,
+    /// Settings not modeled by this crate, as returned by the api.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Settings {
+    /// The logo and result format as a talk or clip config, if either is set.
+    pub fn config(&self) -> Option<Config> {
+        if self.logo.is_none() && self.result_format.is_none() {
+            return None;
+        }
+        let mut config = ConfigBuilder::new();
+        if let Some(logo) = &self.logo {
+            config = config.logo(logo.clone());
+        }
+        if let Some(result_format) = self.result_format {
+            config = config.result_format(result_format);
+        }
+        // build() only validates ranges, which are not set here
+        config.build().ok()
+    }
+}
+
+pub async fn get_settings() -> Result<Settings> {
+    let c = ClientBuilder::new()?
+        .method(GET)?
+        .path(SETTINGS_PATH)?
+        .header(ACCEPT, APPLICATION_JSON)?
+        .build()?;
+
+    let resp = c.send_request(Empty::<Bytes>::new()).await?;
+
+    let settings = serde_json::from_slice::<Settings>(resp.as_ref())?;
+
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_unknown_fields() {
+        let json = serde_json::json!({
+            "webhook": "https://example.com/hook",
+            "tts": {"type": "microsoft", "voice_id": "en-GB-SoniaNeural"},
+            "logo": {"url": "https://example.com/logo.png", "position": [0, 500]},
+            "stitch": true,
+        });
+
+        let settings = serde_json::from_value::<Settings>(json.clone()).unwrap();
+
+        assert_eq!(settings.tts.as_ref().unwrap().voice_id(), "en-GB-SoniaNeural");
+        assert_eq!(settings.other["stitch"], true);
+        assert_eq!(serde_json::to_value(&settings).unwrap(), json);
+        assert_eq!(
+            serde_json::to_value(settings.config().unwrap()).unwrap(),
+            serde_json::json!({"logo": {"url": "https://example.com/logo.png", "position": [0, 500]}})
+        );
+        assert!(Settings::default().config().is_none());
+    }
+}
@@ -0,0 +1,303 @@
+// File: uploads.rs
+// Path: src/endpoints/resources/uploads.rs
+
+use super::*;
+use super::audios::delete_audio;
+use super::images::delete_image;
+use crate::endpoints::video::{animations::get_animation, clips::get_clip, talks::get_talk};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// How long D-ID keeps uploaded images and audios at most, older uploads are
+/// dropped from a registry without being deleted.
+pub const UPLOAD_RETENTION: TimeDelta = TimeDelta::hours(48);
+/// Age after which a janitor deletes uploads by default.
+pub const DEFAULT_MAX_UPLOAD_AGE: TimeDelta = TimeDelta::hours(24);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadKind {
+    Image,
+    Audio,
+}
+
+/// A talk, clip or animation consuming an upload.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", content = "id", rename_all = "lowercase")]
+pub enum UploadJob {
+    Talk(String),
+    Clip(String),
+    Animation(String),
+}
+
+impl UploadJob {
+    async fn is_finished(&self) -> Result<bool> {
+        let status = match self {
+            UploadJob::Talk(id) => get_talk(id).await?.status,
+            UploadJob::Clip(id) => get_clip(id).await?.status,
+            UploadJob::Animation(id) => get_animation(id).await?.status,
+        };
+        Ok(matches!(status.as_str(), "done" | "error" | "rejected"))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrackedUpload {
+    pub kind: UploadKind,
+    pub id: String,
+    pub url: String,
+    pub uploaded_at: DateTime<Utc>,
+    #[serde(default)]
+    pub jobs: Vec<UploadJob>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct RegistryFile {
+    uploads: Vec<TrackedUpload>,
+}
+
+/// Uploaded images and audios, persisted to a local JSON file so they can be
+/// deleted by a `Janitor` even after a restart.
+/// Uploads made with the registry set in `ImageUploadOptions::registry` or
+/// `AudioUploadOptions::registry` are recorded as they succeed; others can be
+/// recorded with `track_image` and `track_audio`.
+#[derive(Debug)]
+pub struct UploadRegistry {
+    path: PathBuf,
+    uploads: Vec<TrackedUpload>,
+}
+
+impl UploadRegistry {
+    /// Loads the registry stored at `path`, or starts an empty one.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let uploads = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<RegistryFile>(&bytes)?.uploads,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Box::new(e)),
+        };
+        Ok(Self { path, uploads })
+    }
+
+    pub fn uploads(&self) -> &[TrackedUpload] {
+        &self.uploads
+    }
+
+    /// Writes the registry next to its file first, so a crash never leaves a
+    /// truncated registry behind.
+    pub fn save(&self) -> Result<()> {
+        let file = RegistryFile {
+            uploads: self.uploads.clone(),
+        };
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&file)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    fn record(&mut self, kind: UploadKind, id: &str, url: &str) {
+        self.uploads.push(TrackedUpload {
+            kind,
+            id: id.to_string(),
+            url: url.to_string(),
+            uploaded_at: Utc::now(),
+            jobs: Vec::new(),
+        });
+    }
+
+    pub fn track_image(&mut self, image: &images::ImageResponse) -> Result<()> {
+        self.record(UploadKind::Image, &image.id, &image.url);
+        self.save()
+    }
+
+    pub fn track_audio(&mut self, audio: &audios::AudioResponse) -> Result<()> {
+        self.record(UploadKind::Audio, &audio.id, &audio.url);
+        self.save()
+    }
+
+    /// Marks the upload with id or url `upload` as consumed by `job`, so a
+    /// janitor deletes it once the job finishes.
+    pub fn attach(&mut self, upload: &str, job: UploadJob) -> Result<()> {
+        let tracked = self
+            .uploads
+            .iter_mut()
+            .find(|u| u.id == upload || u.url == upload)
+            .ok_or_else(|| UploadError::UntrackedUpload(upload.to_string()))?;
+        tracked.jobs.push(job);
+        self.save()
+    }
+
+    pub fn remove(&mut self, ids: &[String]) -> Result<()> {
+        self.uploads.retain(|u| !ids.contains(&u.id));
+        self.save()
+    }
+
+}
+
+#[derive(Debug, Default)]
+pub struct SweepReport {
+    /// Uploads deleted from D-ID.
+    pub deleted: Vec<String>,
+    /// Uploads older than `UPLOAD_RETENTION`, already gone from D-ID.
+    pub expired: Vec<String>,
+    /// Uploads that could not be deleted, with the error, kept for the next sweep.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Deletes tracked uploads once every job consuming them has finished, or
+/// once they are older than `max_age`.
+#[derive(Debug, Clone)]
+pub struct Janitor {
+    max_age: Option<TimeDelta>,
+    after_jobs: bool,
+}
+
+impl Default for Janitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Janitor {
+    pub fn new() -> Self {
+        Self {
+            max_age: Some(DEFAULT_MAX_UPLOAD_AGE),
+            after_jobs: true,
+        }
+    }
+
+    /// `None` keeps uploads until their jobs finish.
+    pub fn max_age(mut self, max_age: Option<TimeDelta>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Whether uploads are deleted as soon as the jobs consuming them finish.
+    pub fn after_jobs(mut self, after_jobs: bool) -> Self {
+        self.after_jobs = after_jobs;
+        self
+    }
+
+    async fn is_due(&self, upload: &TrackedUpload, now: DateTime<Utc>) -> Result<bool> {
+        if self.max_age.is_some_and(|max| now - upload.uploaded_at >= max) {
+            return Ok(true);
+        }
+        if !self.after_jobs || upload.jobs.is_empty() {
+            return Ok(false);
+        }
+        for job in &upload.jobs {
+            if !job.is_finished().await? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn clean(&self, uploads: Vec<TrackedUpload>) -> SweepReport {
+        let now = Utc::now();
+        let mut report = SweepReport::default();
+
+        for upload in uploads {
+            if now - upload.uploaded_at >= UPLOAD_RETENTION {
+                report.expired.push(upload.id);
+                continue;
+            }
+
+            let deleted = match self.is_due(&upload, now).await {
+                Ok(false) => continue,
+                Ok(true) => match upload.kind {
+                    UploadKind::Image => delete_image(&upload.id).await,
+                    UploadKind::Audio => delete_audio(&upload.id).await,
+                },
+                Err(e) => Err(e),
+            };
+            match deleted {
+                Ok(()) => report.deleted.push(upload.id),
+                Err(e) => report.failed.push((upload.id, e.to_string())),
+            }
+        }
+
+        report
+    }
+
+    pub async fn sweep(&self, registry: &mut UploadRegistry) -> Result<SweepReport> {
+        let report = self.clean(registry.uploads.clone()).await;
+        registry.remove(&[report.deleted.clone(), report.expired.clone()].concat())?;
+        Ok(report)
+    }
+
+    /// Sweeps a registry shared with the code that uploads, without holding
+    /// it while talking to the api so uploads are not blocked.
+    pub async fn sweep_shared(&self, registry: &Mutex<UploadRegistry>) -> Result<SweepReport> {
+        let uploads = registry.lock().await.uploads.clone();
+        let report = self.clean(uploads).await;
+        let removed = [report.deleted.clone(), report.expired.clone()].concat();
+        registry.lock().await.remove(&removed)?;
+        Ok(report)
+    }
+
+    /// Sweeps `registry` every `interval` in the background.
+    /// Uploads that fail to be deleted are retried on the next sweep; the task
+    /// only stops, with the error, when the registry cannot be saved.
+    pub fn spawn(
+        self,
+        registry: Arc<Mutex<UploadRegistry>>,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<Result<()>> {
+        tokio::spawn(async move {
+            loop {
+                self.sweep_shared(&registry).await?;
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("d-id-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn registry_is_persisted() {
+        let path = registry_path("registry");
+        let mut registry = UploadRegistry::open(&path).unwrap();
+        registry.record(UploadKind::Image, "img_1", "s3://bucket/img_1.jpg");
+        registry.record(UploadKind::Audio, "aud_1", "s3://bucket/aud_1.wav");
+        registry.attach("s3://bucket/aud_1.wav", UploadJob::Talk("tlk_1".to_string())).unwrap();
+
+        let mut reopened = UploadRegistry::open(&path).unwrap();
+        assert_eq!(reopened.uploads().len(), 2);
+        assert_eq!(reopened.uploads()[1].jobs, vec![UploadJob::Talk("tlk_1".to_string())]);
+
+        let err = reopened.attach("img_typo", UploadJob::Talk("tlk_1".to_string())).unwrap_err();
+        assert!(matches!(err.downcast_ref::<UploadError>(), Some(UploadError::UntrackedUpload(_))));
+
+        reopened.remove(&["img_1".to_string()]).unwrap();
+        assert_eq!(UploadRegistry::open(&path).unwrap().uploads()[0].id, "aud_1");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn expired_uploads_are_dropped_without_deleting() {
+        let path = registry_path("janitor");
+        let mut registry = UploadRegistry::open(&path).unwrap();
+        registry.record(UploadKind::Image, "old", "s3://bucket/old.jpg");
+        registry.uploads[0].uploaded_at = Utc::now() - TimeDelta::hours(72);
+        registry.record(UploadKind::Image, "new", "s3://bucket/new.jpg");
+
+        // A fresh upload without jobs is not due, so no request is sent
+        let report = Janitor::new().sweep(&mut registry).await.unwrap();
+
+        assert_eq!(report.expired, vec!["old"]);
+        assert!(report.deleted.is_empty() && report.failed.is_empty());
+        assert_eq!(registry.uploads().len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+// File: voice_resolver.rs
+// Path: src/endpoints/resources/voice_resolver.rs
+
+use super::*;
+use super::voices::{Gender, Voice, VoiceCatalog, VoiceProvider};
+use crate::endpoints::video::TTSProvider;
+use crate::error::VoiceError;
+
+/// The language, script and region subtags of a BCP-47 tag such as
+/// `zh-Hant-TW`. Variants and extensions are ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageTag {
+    pub language: String,
+    pub script: Option<String>,
+    pub region: Option<String>,
+}
+
+impl LanguageTag {
+    pub fn parse(tag: &str) -> std::result::Result<Self, VoiceError> {
+        let invalid = || VoiceError::InvalidLanguageTag(tag.to_string());
+        let mut subtags = tag.split(['-', '_']);
+
+        let language = subtags
+            .next()
+            .filter(|l| (2..=3).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()))
+            .ok_or_else(invalid)?
+            .to_ascii_lowercase();
+
+        let mut script = None;
+        let mut region = None;
+        for subtag in subtags {
+            let alphabetic = subtag.chars().all(|c| c.is_ascii_alphabetic());
+            let numeric = subtag.chars().all(|c| c.is_ascii_digit());
+            if script.is_none() && region.is_none() && subtag.len() == 4 && alphabetic {
+                let (first, rest) = subtag.split_at(1);
+                script = Some(first.to_ascii_uppercase() + &rest.to_ascii_lowercase());
+            } else if region.is_none() && ((subtag.len() == 2 && alphabetic) || (subtag.len() == 3 && numeric)) {
+                region = Some(subtag.to_ascii_uppercase());
+            } else if subtag.is_empty() {
+                return Err(invalid());
+            } else {
+                break;
+            }
+        }
+
+        Ok(Self { language, script, region })
+    }
+
+    /// The explicit script, or the one implied by the region for Chinese.
+    pub fn effective_script(&self) -> Option<&str> {
+        if let Some(script) = &self.script {
+            return Some(script);
+        }
+        match (self.language.as_str(), self.region.as_deref()) {
+            ("zh", Some("TW" | "HK" | "MO")) => Some("Hant"),
+            ("zh", _) => Some("Hans"),
+            _ => None,
+        }
+    }
+}
+
+/// How closely a resolved voice matches the requested tag, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchLevel {
+    /// Same language, script and region.
+    Exact,
+    /// Same language and region.
+    Region,
+    /// Same language and script, in another region.
+    Script,
+    /// Same language only.
+    Language,
+    /// The default voice of the resolver.
+    Default,
+}
+
+fn match_level(wanted: &LanguageTag, voice: &LanguageTag) -> Option<MatchLevel> {
+    if wanted.language != voice.language {
+        return None;
+    }
+    let same_script = wanted.effective_script() == voice.effective_script();
+    let same_region = wanted.region.is_some() && wanted.region == voice.region;
+    Some(match (same_region, same_script) {
+        (true, true) => MatchLevel::Exact,
+        (true, false) => MatchLevel::Region,
+        (false, true) if wanted.effective_script().is_some() => MatchLevel::Script,
+        _ => MatchLevel::Language,
+    })
+}
+
+/// Soft preferences, used to rank the voices speaking the requested locale.
+#[derive(Debug, Clone, Default)]
+pub struct VoicePreferences {
+    pub gender: Option<Gender>,
+    pub provider: Option<VoiceProvider>,
+    /// Voices with this style are preferred and speak in it.
+    pub style: Option<String>,
+}
+
+impl VoicePreferences {
+    fn score(&self, voice: &Voice, main_locale: bool) -> u32 {
+        let mut score = 0;
+        if self.gender.as_ref().is_some_and(|g| *g == voice.gender) {
+            score += 8;
+        }
+        if self.provider.as_ref().is_some_and(|p| *p == voice.provider) {
+            score += 4;
+        }
+        if self.style.as_ref().is_some_and(|s| voice.has_style(s)) {
+            score += 2;
+        }
+        if main_locale {
+            score += 1;
+        }
+        score
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedVoice {
+    /// `None` when the default voice was used.
+    pub voice: Option<Voice>,
+    pub provider: TTSProvider,
+    pub level: MatchLevel,
+}
+
+/// Picks the voice best matching `tag` among `voices`.
+/// Voices are matched on the region first, then the script, then only the
+/// language; within a level the one best meeting `preferences` wins.
+pub fn resolve_voice(
+    voices: &[Voice],
+    tag: &str,
+    preferences: &VoicePreferences,
+) -> std::result::Result<Option<ResolvedVoice>, VoiceError> {
+    let wanted = LanguageTag::parse(tag)?;
+
+    let mut best: Option<(MatchLevel, u32, &Voice)> = None;
+    for voice in voices {
+        if voice.tts_provider(None).is_none() {
+            continue;
+        }
+        let level = voice
+            .locales()
+            .enumerate()
+            .filter_map(|(i, locale)| {
+                let level = match_level(&wanted, &LanguageTag::parse(locale).ok()?)?;
+                Some((level, i == 0))
+            })
+            .min_by_key(|(level, main)| (*level, !main));
+        let Some((level, main)) = level else {
+            continue;
+        };
+
+        let score = preferences.score(voice, main);
+        let better = best.is_none_or(|(best_level, best_score, _)| {
+            level < best_level || (level == best_level && score > best_score)
+        });
+        if better {
+            best = Some((level, score, voice));
+        }
+    }
+
+    Ok(best.and_then(|(level, _, voice)| {
+        Some(ResolvedVoice {
+            voice: Some(voice.clone()),
+            provider: voice.tts_provider(preferences.style.as_deref())?,
+            level,
+        })
+    }))
+}
+
+/// Resolves BCP-47 locales to voices of a `VoiceCatalog`, falling back to a
+/// default voice when no voice speaks the language.
+#[derive(Debug, Default)]
+pub struct VoiceResolver {
+    catalog: VoiceCatalog,
+    default_provider: Option<TTSProvider>,
+}
+
+impl VoiceResolver {
+    pub fn new(catalog: VoiceCatalog) -> Self {
+        Self {
+            catalog,
+            default_provider: None,
+        }
+    }
+
+    pub fn default_provider(mut self, provider: TTSProvider) -> Self {
+        self.default_provider = Some(provider);
+        self
+    }
+
+    pub async fn resolve(&mut self, tag: &str, preferences: &VoicePreferences) -> Result<ResolvedVoice> {
+        let voices = self.catalog.voices().await?;
+        if let Some(resolved) = resolve_voice(voices, tag, preferences)? {
+            return Ok(resolved);
+        }
+
+        match &self.default_provider {
+            Some(provider) => Ok(ResolvedVoice {
+                voice: None,
+                provider: provider.clone(),
+                level: MatchLevel::Default,
+            }),
+            None => Err(Box::new(VoiceError::NoMatchingVoice(tag.to_string()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::support::fixtures::voice;
+
+    #[test]
+    fn tags_are_parsed() {
+        assert_eq!(
+            LanguageTag::parse("zh-hant-tw").unwrap(),
+            LanguageTag {
+                language: "zh".to_string(),
+                script: Some("Hant".to_string()),
+                region: Some("TW".to_string()),
+            }
+        );
+        assert_eq!(LanguageTag::parse("es-419").unwrap().region.as_deref(), Some("419"));
+        assert_eq!(LanguageTag::parse("pt_BR").unwrap().region.as_deref(), Some("BR"));
+        assert!(LanguageTag::parse("english").is_err());
+        assert!(LanguageTag::parse("").is_err());
+    }
+
+    #[test]
+    fn voices_fall_back_from_region_to_language() {
+        let voices = vec![
+            voice("pt-PT-RaquelNeural", "pt-PT", "Female", "microsoft", &[]),
+            voice("pt-BR-AntonioNeural", "pt-BR", "Male", "microsoft", &[]),
+            voice("zh-CN-XiaoxiaoNeural", "zh-CN", "Female", "microsoft", &["cheerful"]),
+            voice("zh-HK-HiuMaanNeural", "zh-HK", "Female", "microsoft", &[]),
+            voice("de-DE-KatjaNeural", "de-DE", "Female", "microsoft", &[]),
+        ];
+        let female = VoicePreferences {
+            gender: Some(Gender::Female),
+            ..Default::default()
+        };
+        let resolve = |tag: &str, prefs: &VoicePreferences| {
+            resolve_voice(&voices, tag, prefs)
+                .unwrap()
+                .map(|r| (r.provider.voice_id().to_string(), r.level))
+        };
+
+        // The region outranks the gender preference
+        assert_eq!(resolve("pt-BR", &female), Some(("pt-BR-AntonioNeural".to_string(), MatchLevel::Exact)));
+        assert_eq!(resolve("zh-Hant-TW", &female), Some(("zh-HK-HiuMaanNeural".to_string(), MatchLevel::Script)));
+        assert_eq!(resolve("de-AT", &female), Some(("de-DE-KatjaNeural".to_string(), MatchLevel::Language)));
+        assert_eq!(resolve("fr-FR", &female), None);
+
+        let cheerful = VoicePreferences {
+            style: Some("cheerful".to_string()),
+            ..Default::default()
+        };
+        let resolved = resolve_voice(&voices, "zh", &cheerful).unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_value(&resolved.provider).unwrap()["voice_config"]["style"],
+            "cheerful"
+        );
+    }
+}
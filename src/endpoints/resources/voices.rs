@@ -2,21 +2,188 @@
 // Path: src/endpoints/resources/voices.rs
 
 use super::*;
+use crate::endpoints::video::TTSProvider;
+pub use crate::support::cache::DEFAULT_CATALOG_TTL;
+use crate::support::cache::TtlCache;
+use serde::Deserializer;
+use std::time::Duration;
 
 const VOICES_PATH: &str = "/tts/voices";
 
 pub type Voices = Vec<Voice>;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "String", into = "String")]
+pub enum Gender {
+    Female,
+    Male,
+    Other(String),
+}
+
+impl From<String> for Gender {
+    fn from(s: String) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "female" => Gender::Female,
+            "male" => Gender::Male,
+            _ => Gender::Other(s),
+        }
+    }
+}
+
+impl From<Gender> for String {
+    fn from(gender: Gender) -> Self {
+        match gender {
+            Gender::Female => "female".to_string(),
+            Gender::Male => "male".to_string(),
+            Gender::Other(s) => s,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "String", into = "String")]
+pub enum Access {
+    Public,
+    Premium,
+    Private,
+    Other(String),
+}
+
+impl From<String> for Access {
+    fn from(s: String) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "public" => Access::Public,
+            "premium" => Access::Premium,
+            "private" => Access::Private,
+            _ => Access::Other(s),
+        }
+    }
+}
+
+impl From<Access> for String {
+    fn from(access: Access) -> Self {
+        match access {
+            Access::Public => "public".to_string(),
+            Access::Premium => "premium".to_string(),
+            Access::Private => "private".to_string(),
+            Access::Other(s) => s,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "String", into = "String")]
+pub enum VoiceProvider {
+    Microsoft,
+    ElevenLabs,
+    Amazon,
+    Afflorithmics,
+    Other(String),
+}
+
+impl From<String> for VoiceProvider {
+    fn from(s: String) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "microsoft" => VoiceProvider::Microsoft,
+            "elevenlabs" => VoiceProvider::ElevenLabs,
+            "amazon" => VoiceProvider::Amazon,
+            "afflorithmics" => VoiceProvider::Afflorithmics,
+            _ => VoiceProvider::Other(s),
+        }
+    }
+}
+
+impl From<VoiceProvider> for String {
+    fn from(provider: VoiceProvider) -> Self {
+        match provider {
+            VoiceProvider::Microsoft => "microsoft".to_string(),
+            VoiceProvider::ElevenLabs => "elevenlabs".to_string(),
+            VoiceProvider::Amazon => "amazon".to_string(),
+            VoiceProvider::Afflorithmics => "afflorithmics".to_string(),
+            VoiceProvider::Other(s) => s,
+        }
+    }
+}
+
+/// An additional language spoken by a multilingual voice.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoiceLanguage {
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub locale: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Voice {
-    id: String,
-    name: String,
-    gender: String,
-    locale: String,
-    language: String,
-    access: String,
-    provider: String,
-    //styles: Vec<Option<String>>,
+    pub id: String,
+    pub name: String,
+    pub gender: Gender,
+    pub locale: String,
+    pub language: String,
+    pub access: Access,
+    pub provider: VoiceProvider,
+    #[serde(default, deserialize_with = "non_null")]
+    pub styles: Vec<String>,
+    #[serde(default, deserialize_with = "non_null")]
+    pub languages: Vec<VoiceLanguage>,
+}
+
+/// Accepts null lists and null entries, as sent for voices without styles.
+fn non_null<'de, D, T>(deserializer: D) -> std::result::Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let values = Option::<Vec<Option<T>>>::deserialize(deserializer)?;
+    Ok(values.unwrap_or_default().into_iter().flatten().collect())
+}
+
+impl Voice {
+    /// The main locale of the voice followed by those of its other languages.
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.locale.as_str())
+            .chain(self.languages.iter().map(|l| l.locale.as_str()))
+            .filter(|l| !l.is_empty())
+    }
+
+    pub fn speaks(&self, locale: &str) -> bool {
+        self.locales().any(|l| l.eq_ignore_ascii_case(locale))
+    }
+
+    pub fn has_style(&self, style: &str) -> bool {
+        self.style(style).is_some()
+    }
+
+    /// The entry of `styles` matching `style` regardless of case, as the
+    /// provider expects it.
+    pub fn style(&self, style: &str) -> Option<&str> {
+        self.styles
+            .iter()
+            .find(|s| s.eq_ignore_ascii_case(style))
+            .map(String::as_str)
+    }
+
+    /// Whether this looks like a Microsoft neural voice. This is a heuristic:
+    /// the api has no such flag, so it only checks for the `Neural` suffix
+    /// Microsoft gives the ids of its neural voices.
+    pub fn is_neural(&self) -> bool {
+        self.id.ends_with("Neural")
+    }
+
+    /// The provider to set on a text script to speak with this voice, in
+    /// `style` for Microsoft voices that support it. `None` for providers this
+    /// crate does not know.
+    pub fn tts_provider(&self, style: Option<&str>) -> Option<TTSProvider> {
+        let style = style.and_then(|s| self.style(s));
+        match self.provider {
+            VoiceProvider::Microsoft => Some(TTSProvider::microsoft(&self.id, style)),
+            VoiceProvider::ElevenLabs => Some(TTSProvider::eleven_labs(&self.id)),
+            VoiceProvider::Amazon => Some(TTSProvider::amazon(&self.id)),
+            VoiceProvider::Afflorithmics => Some(TTSProvider::afflorithmics(&self.id)),
+            VoiceProvider::Other(_) => None,
+        }
+    }
 }
 
 pub async fn get_voices() -> Result<Voices> {
@@ -28,7 +195,178 @@ pub async fn get_voices() -> Result<Voices> {
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let voices = serde_json::from_slice::<Voices>(&resp.as_ref())?;
+    let voices = serde_json::from_slice::<Voices>(resp.as_ref())?;
 
     Ok(voices)
 }
+
+/// Filters for `VoiceCatalog::query`. Unset filters match every voice.
+#[derive(Debug, Clone, Default)]
+pub struct VoiceQuery {
+    gender: Option<Gender>,
+    locale: Option<String>,
+    provider: Option<VoiceProvider>,
+    access: Option<Access>,
+    neural: Option<bool>,
+    style: Option<String>,
+}
+
+impl VoiceQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gender(mut self, gender: Gender) -> Self {
+        self.gender = Some(gender);
+        self
+    }
+
+    /// Matches voices speaking `locale`, such as `en-GB`, in any of their languages.
+    pub fn locale(mut self, locale: &str) -> Self {
+        self.locale = Some(locale.to_string());
+        self
+    }
+
+    pub fn provider(mut self, provider: VoiceProvider) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    pub fn access(mut self, access: Access) -> Self {
+        self.access = Some(access);
+        self
+    }
+
+    /// Matches on `Voice::is_neural`, which is guessed from the voice id.
+    pub fn neural(mut self, neural: bool) -> Self {
+        self.neural = Some(neural);
+        self
+    }
+
+    /// Matches voices supporting `style`, which the providers returned by
+    /// `VoiceCatalog::providers` then speak in.
+    pub fn style(mut self, style: &str) -> Self {
+        self.style = Some(style.to_string());
+        self
+    }
+
+    pub fn matches(&self, voice: &Voice) -> bool {
+        self.gender.as_ref().is_none_or(|g| *g == voice.gender)
+            && self.locale.as_ref().is_none_or(|l| voice.speaks(l))
+            && self.provider.as_ref().is_none_or(|p| *p == voice.provider)
+            && self.access.as_ref().is_none_or(|a| *a == voice.access)
+            && self.neural.is_none_or(|n| n == voice.is_neural())
+            && self.style.as_ref().is_none_or(|s| voice.has_style(s))
+    }
+}
+
+/// Voices loaded on first use and kept for `ttl` so repeated lookups do not
+/// hit the api.
+#[derive(Debug)]
+pub struct VoiceCatalog {
+    voices: TtlCache<Voices>,
+}
+
+impl Default for VoiceCatalog {
+    fn default() -> Self {
+        Self::new(DEFAULT_CATALOG_TTL)
+    }
+}
+
+impl VoiceCatalog {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            voices: TtlCache::new(ttl),
+        }
+    }
+
+    /// Drops the cached voices, so the next lookup reloads them from the api.
+    pub fn invalidate(&mut self) {
+        self.voices.invalidate();
+    }
+
+    pub async fn voices(&mut self) -> Result<&[Voice]> {
+        Ok(self.voices.get_or_load(get_voices).await?)
+    }
+
+    pub async fn voice(&mut self, voice_id: &str) -> Result<Option<Voice>> {
+        Ok(self.voices().await?.iter().find(|v| v.id == voice_id).cloned())
+    }
+
+    pub async fn query(&mut self, query: &VoiceQuery) -> Result<Vec<Voice>> {
+        Ok(self
+            .voices()
+            .await?
+            .iter()
+            .filter(|v| query.matches(v))
+            .cloned()
+            .collect())
+    }
+
+    /// Providers for the voices matching `query`, speaking in its style if set.
+    pub async fn providers(&mut self, query: &VoiceQuery) -> Result<Vec<TTSProvider>> {
+        let style = query.style.clone();
+        Ok(self
+            .query(query)
+            .await?
+            .iter()
+            .filter_map(|v| v.tts_provider(style.as_deref()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voices() -> Voices {
+        serde_json::from_value(serde_json::json!([
+            {
+                "id": "en-GB-SoniaNeural", "name": "Sonia", "gender": "Female",
+                "locale": "en-GB", "language": "English (United Kingdom)",
+                "access": "public", "provider": "microsoft",
+                "styles": ["cheerful", "sad", null],
+            },
+            {
+                "id": "en-GB-LibbyNeural", "name": "Libby", "gender": "Female",
+                "locale": "en-GB", "language": "English (United Kingdom)",
+                "access": "public", "provider": "microsoft", "styles": null,
+            },
+            {
+                "id": "21m00Tcm4TlvDq8ikWAM", "name": "Rachel", "gender": "female",
+                "locale": "en-US", "language": "English (United States)",
+                "access": "premium", "provider": "elevenlabs",
+                "languages": [{"language": "English (United Kingdom)", "locale": "en-GB"}],
+            },
+        ]))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn catalog_queries_return_providers() {
+        let mut catalog = VoiceCatalog::default();
+        catalog.voices.insert(voices());
+
+        let query = VoiceQuery::new()
+            .gender(Gender::Female)
+            .locale("en-gb")
+            .neural(true)
+            .style("Cheerful");
+        let providers = catalog.providers(&query).await.unwrap();
+
+        assert_eq!(providers.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&providers[0]).unwrap(),
+            serde_json::json!({
+                "type": "microsoft",
+                "voice_id": "en-GB-SoniaNeural",
+                "voice_config": {"style": "cheerful"},
+            })
+        );
+
+        let en_gb = catalog.query(&VoiceQuery::new().locale("en-GB")).await.unwrap();
+        assert_eq!(en_gb.len(), 3);
+        assert_eq!(en_gb[2].access, Access::Premium);
+        assert_eq!(en_gb[2].provider, VoiceProvider::ElevenLabs);
+    }
+}
@@ -2,6 +2,7 @@
 // Path: src/endpoints/video/animations.rs
 
 use super::*;
+use super::defaults::RequestDefaults;
 
 const ANIMATIONS_PATH: &str = "/animations";
 
@@ -9,8 +10,8 @@ const ANIMATIONS_PATH: &str = "/animations";
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AnimationRequestBody {
     source_url: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
-    driver_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    driver_url: Option<DriverUrl>,
     #[serde(skip_serializing_if = "String::is_empty")]
     result_url: String,
     #[serde(skip_serializing_if = "String::is_empty")]
@@ -24,6 +25,20 @@ pub struct AnimationRequestBody {
 }
 
 impl AnimationRequestBody {
+    /// This animation with the webhook, result url and config it leaves
+    /// unset taken from `defaults`. Animations take no voice or persist flag.
+    pub fn with_defaults(mut self, defaults: &RequestDefaults) -> Self {
+        defaults.fill_webhook(&mut self.webhook);
+        defaults.fill_config(&mut self.config);
+        defaults.fill_result_url(&mut self.result_url, self.config.as_ref());
+        self
+    }
+
+    /// Sends this animation with the values it leaves unset taken from `defaults`.
+    pub async fn create_animation_with(self, defaults: &RequestDefaults) -> Result<PostAnimationResponse> {
+        self.with_defaults(defaults).create_animation().await
+    }
+
     pub async fn create_animation(&self) -> Result<PostAnimationResponse> {
         let c = ClientBuilder::new()?
             .method(POST)?
@@ -35,7 +50,7 @@ impl AnimationRequestBody {
 
         let resp = c.send_request(Full::<Bytes>::new(body.into())).await?;
 
-        let animation_resp = serde_json::from_slice::<PostAnimationResponse>(&resp.as_ref())?;
+        let animation_resp = serde_json::from_slice::<PostAnimationResponse>(resp.as_ref())?;
 
         Ok(animation_resp)
     }
@@ -45,7 +60,7 @@ impl AnimationRequestBody {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AnimationRequestBodyBuilder {
     source_url: Option<String>,
-    driver_url: Option<String>,
+    driver_url: Option<DriverUrl>,
     result_url: Option<String>,
     webhook: Option<String>,
     user_data: Option<String>,
@@ -53,6 +68,12 @@ pub struct AnimationRequestBodyBuilder {
     config: Option<Config>,
 }
 
+impl Default for AnimationRequestBodyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AnimationRequestBodyBuilder {
     pub fn new() -> Self {
         Self {
@@ -71,8 +92,8 @@ impl AnimationRequestBodyBuilder {
         self
     }
 
-    pub fn driver_url(mut self, driver_url: String) -> Self {
-        self.driver_url = Some(driver_url);
+    pub fn driver_url(mut self, driver_url: impl Into<DriverUrl>) -> Self {
+        self.driver_url = Some(driver_url.into());
         self
     }
 
@@ -106,7 +127,7 @@ impl AnimationRequestBodyBuilder {
 
         Ok(AnimationRequestBody {
             source_url,
-            driver_url: self.driver_url.unwrap_or_default(),
+            driver_url: self.driver_url,
             result_url: self.result_url.unwrap_or_default(),
             webhook: self.webhook.unwrap_or_default(),
             user_data: self.user_data.unwrap_or_default(),
@@ -153,13 +174,13 @@ pub struct GetAnimationsResponse {
 pub async fn get_animation(animation_id: &str) -> Result<Animation> {
     let c = ClientBuilder::new()?
         .method(GET)?
-        .path(&format!("{}/{}", ANIMATIONS_PATH, animation_id))?
+        .path(format!("{}/{}", ANIMATIONS_PATH, animation_id))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let animation_resp = serde_json::from_slice::<Animation>(&resp.as_ref())?;
+    let animation_resp = serde_json::from_slice::<Animation>(resp.as_ref())?;
 
     Ok(animation_resp)
 }
@@ -173,7 +194,7 @@ pub async fn get_animations() -> Result<GetAnimationsResponse> {
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let animations = serde_json::from_slice::<GetAnimationsResponse>(&resp.as_ref())?;
+    let animations = serde_json::from_slice::<GetAnimationsResponse>(resp.as_ref())?;
 
     Ok(animations)
 }
@@ -181,7 +202,7 @@ pub async fn get_animations() -> Result<GetAnimationsResponse> {
 pub async fn delete_animation(animation_id: &str) -> Result<()> {
     let c = ClientBuilder::new()?
         .method(DELETE)?
-        .path(&format!("{}/{}", ANIMATIONS_PATH, animation_id))?
+        .path(format!("{}/{}", ANIMATIONS_PATH, animation_id))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
@@ -2,37 +2,38 @@
 // Path: src/endpoints/video/clips.rs
 
 use super::*;
+use super::defaults::RequestDefaults;
 
 const CLIPS_PATH: &str = "/clips";
 const PRESENTERS_PATH: &str = "/presenters";
 const DRIVERS_PATH: &str = "/drivers";
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GetPresentersResponse {
-    presenters: Vec<Presenter>,
+    pub presenters: Vec<Presenter>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Presenter {
-    presenter_id: String,
-    driver_id: String,
-    gender: String,
-    owner_id: String,
-    preview_url: String,
-    modified_at: String,
+    pub presenter_id: String,
+    pub driver_id: String,
+    pub gender: String,
+    pub owner_id: String,
+    pub preview_url: String,
+    pub modified_at: String,
     //video_url: String,
 }
 
 pub async fn get_presenters() -> Result<GetPresentersResponse> {
     let c = ClientBuilder::new()?
         .method(GET)?
-        .path(&format!("{}{}", CLIPS_PATH, PRESENTERS_PATH))?
+        .path(format!("{}{}", CLIPS_PATH, PRESENTERS_PATH))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let presenters = serde_json::from_slice::<GetPresentersResponse>(&resp.as_ref())?;
+    let presenters = serde_json::from_slice::<GetPresentersResponse>(resp.as_ref())?;
 
     Ok(presenters)
 }
@@ -40,13 +41,13 @@ pub async fn get_presenters() -> Result<GetPresentersResponse> {
 pub async fn get_presenter(id: &str) -> Result<Presenter> {
     let c = ClientBuilder::new()?
         .method(GET)?
-        .path(&format!("{}{}/{}", CLIPS_PATH, PRESENTERS_PATH, id))?
+        .path(format!("{}{}/{}", CLIPS_PATH, PRESENTERS_PATH, id))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let presenter = serde_json::from_slice::<Presenter>(&resp.as_ref())?;
+    let presenter = serde_json::from_slice::<Presenter>(resp.as_ref())?;
 
     Ok(presenter)
 }
@@ -77,34 +78,161 @@ pub struct ClipRequestBody {
     #[serde(skip_serializing_if = "String::is_empty")]
     raw_result_url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    persist: Option<bool>
+    persist: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct PresenterConfig{
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PresenterConfig {
     crop: Crop,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Crop {
-    r#type: String,
-    rectangle: Rectangle,
+    r#type: CropType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rectangle: Option<Rectangle>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CropType {
+    Wide,
+    Rectangle,
+}
+
+/// Crop bounds relative to the presenter video, each between 0 and 1.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct Rectangle {
-    bottom: i64,
-    left: i64,
-    right: i64,
-    top: i64,
+    bottom: f32,
+    left: f32,
+    right: f32,
+    top: f32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Debug, Default)]
+pub struct PresenterConfigBuilder {
+    crop_type: Option<CropType>,
+    rectangle: Option<Rectangle>,
+}
+
+impl PresenterConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn crop_type(mut self, crop_type: CropType) -> Self {
+        self.crop_type = Some(crop_type);
+        self
+    }
+
+    /// Sets the crop bounds, implying `CropType::Rectangle` unless another type is set.
+    pub fn rectangle(mut self, top: f32, left: f32, bottom: f32, right: f32) -> Self {
+        self.rectangle = Some(Rectangle { bottom, left, right, top });
+        self
+    }
+
+    pub fn build(self) -> Result<PresenterConfig> {
+        if let Some(r) = &self.rectangle {
+            check_range("top", Some(r.top), 0.0, 1.0)?;
+            check_range("left", Some(r.left), 0.0, 1.0)?;
+            check_range("bottom", Some(r.bottom), 0.0, 1.0)?;
+            check_range("right", Some(r.right), 0.0, 1.0)?;
+            if r.left >= r.right || r.top >= r.bottom {
+                return Err(Box::new(RequestBodyBuildError::InvalidRectangle));
+            }
+        }
+
+        let r#type = self.crop_type.unwrap_or(match self.rectangle {
+            Some(_) => CropType::Rectangle,
+            None => CropType::Wide,
+        });
+
+        Ok(PresenterConfig {
+            crop: Crop {
+                r#type,
+                rectangle: self.rectangle,
+            },
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Background {
-    color: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_url: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct BackgroundBuilder {
+    color: Option<String>,
+    source_url: Option<String>,
+}
+
+impl BackgroundBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Solid color as a hex string, e.g. `#00FF00`.
+    pub fn color(mut self, color: &str) -> Self {
+        self.color = Some(color.to_string());
+        self
+    }
+
+    /// Url of a background image or video.
+    pub fn source_url(mut self, source_url: &str) -> Self {
+        self.source_url = Some(source_url.to_string());
+        self
+    }
+
+    pub fn build(self) -> Result<Background> {
+        if self.color.is_none() && self.source_url.is_none() {
+            return Err(Box::new(RequestBodyBuildError::BackgroundNotSet));
+        }
+
+        if let Some(color) = &self.color {
+            let valid = color.strip_prefix('#').is_some_and(|hex| {
+                matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+            });
+            if !valid {
+                return Err(Box::new(RequestBodyBuildError::InvalidColor(color.clone())));
+            }
+        }
+
+        Ok(Background {
+            color: self.color,
+            source_url: self.source_url,
+        })
+    }
 }
 
 impl ClipRequestBody {
+    pub fn script(&self) -> &Script {
+        &self.script
+    }
+
+    pub fn config(&self) -> Option<&Config> {
+        self.config.as_ref()
+    }
+
+    /// This clip with the values it leaves unset taken from `defaults`,
+    /// including a result url of its own under their prefix.
+    pub fn with_defaults(mut self, defaults: &RequestDefaults) -> Self {
+        defaults.fill_webhook(&mut self.webhook);
+        defaults.fill_persist(&mut self.persist);
+        defaults.fill_config(&mut self.config);
+        defaults.fill_result_url(&mut self.result_url, self.config.as_ref());
+        defaults.fill_provider(&mut self.script);
+        self
+    }
+
+    /// Sends this clip with the values it leaves unset taken from `defaults`.
+    pub async fn create_with(self, defaults: &RequestDefaults) -> Result<CreateClipResponse> {
+        self.with_defaults(defaults).create().await
+    }
+
     pub async fn create(&self) -> Result<CreateClipResponse> {
         let c = ClientBuilder::new()?
             .method(POST)?
@@ -115,7 +243,7 @@ impl ClipRequestBody {
 
         let resp = c.send_request(Full::<Bytes>::new(serde_json::to_string(&self)?.into())).await?;
 
-        let clip_resp = serde_json::from_slice::<CreateClipResponse>(&resp.as_ref())?;
+        let clip_resp = serde_json::from_slice::<CreateClipResponse>(resp.as_ref())?;
 
         Ok(clip_resp)
     }
@@ -139,6 +267,12 @@ pub struct ClipRequestBodyBuilder {
 }
 
 
+impl Default for ClipRequestBodyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ClipRequestBodyBuilder {
     pub fn new() -> Self {
         Self {
@@ -162,10 +296,7 @@ impl ClipRequestBodyBuilder {
         let script = Script::Text {
             r#type: "text".to_string(),
             subtitles: false,
-            provider: Some(TTSProvider::MicrosoftTTS {
-                r#type: "microsoft".to_string(),
-                voice_id: "en-US-JennyNeural".to_string(),
-            }),
+            provider: None,
             input: "".to_string(),
             ssml: false,
         };
@@ -305,6 +436,8 @@ impl ClipRequestBodyBuilder {
         Ok(self)
     }
 
+    /// Voice of the text script. Without one the clip speaks with the voice
+    /// of the defaults it is sent with, or else with D-ID's default voice.
     pub fn provider(mut self, provider: TTSProvider) -> Result<Self> {
         if let Some(Script::Text { provider: p, .. }) = self.script.as_mut() {
             *p = Some(provider);
@@ -319,10 +452,33 @@ impl ClipRequestBodyBuilder {
         Ok(self)
     }
 
+    /// Checks the driver against the drivers available for the presenter, so
+    /// mismatches fail before the clip is queued.
+    /// When no driver is set, the presenter's default driver is used.
+    pub async fn validate_driver(mut self) -> Result<Self> {
+        let presenter_id = self.presenter_id.as_deref().ok_or(RequestBodyBuildError::PresenterIdNotSet)?;
+        let drivers = get_presenter_drivers(presenter_id).await?;
+
+        let default_driver = match self.driver_id {
+            Some(_) => None,
+            None => Some(get_presenter(presenter_id).await?.driver_id),
+        };
+
+        let driver_id = check_driver(
+            presenter_id,
+            self.driver_id.as_deref().or(default_driver.as_deref()),
+            &drivers.clips_drivers,
+        )?;
+        self.driver_id = Some(driver_id);
+
+        Ok(self)
+    }
+
     pub fn build(self) -> Result<ClipRequestBody> {
         let presenter_id = self.presenter_id.ok_or(RequestBodyBuildError::PresenterIdNotSet)?;
         let script = self.script.ok_or(RequestBodyBuildError::ScriptNotSet)?;
 
+
         Ok(ClipRequestBody {
             presenter_id,
             script,
@@ -349,6 +505,9 @@ pub struct CreateClipResponse {
     status: String,
 }
 
+/// Lists the clips of the account.
+/// Entries that cannot be parsed are reported in `GetClipsResponse::malformed`
+/// instead of failing the whole list.
 pub async fn get_clips() -> Result<GetClipsResponse> {
     let c = ClientBuilder::new()?
         .method(GET)?
@@ -358,46 +517,139 @@ pub async fn get_clips() -> Result<GetClipsResponse> {
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let clips = serde_json::from_slice::<GetClipsResponse>(&resp.as_ref())?;
+    let clips = serde_json::from_slice::<GetClipsResponse>(resp.as_ref())?;
 
     Ok(clips)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
 pub struct GetClipsResponse {
-    clips: Vec<Clip>,
+    pub clips: Vec<Clip>,
+    #[serde(skip)]
+    pub malformed: Vec<MalformedClip>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A listed clip that could not be parsed into a `Clip`.
+#[derive(Debug)]
+pub struct MalformedClip {
+    pub id: Option<String>,
+    pub error: String,
+    pub raw: serde_json::Value,
+}
+
+impl<'de> Deserialize<'de> for GetClipsResponse {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct RawClips {
+            clips: Vec<serde_json::Value>,
+        }
+
+        let raw = RawClips::deserialize(deserializer)?;
+
+        let mut clips = Vec::new();
+        let mut malformed = Vec::new();
+        for value in raw.clips {
+            match Clip::deserialize(&value) {
+                Ok(clip) => clips.push(clip),
+                Err(e) => malformed.push(MalformedClip {
+                    id: value.get("id").and_then(|id| id.as_str()).map(|id| id.to_string()),
+                    error: e.to_string(),
+                    raw: value,
+                }),
+            }
+        }
+
+        Ok(Self { clips, malformed })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Clip {
-    id: String,
-    owner_id: String,
-    audio_url: String,
-    created_at: String,
-    created_by: String,
-    modified_at: String,
-    started_at: String,
-    completed_at: String,
-    status: String,
-    presenter_id: String,
-    driver_id: String,
-    config: Config,
-    name: String,
-    webhook: String,
-    result_url: String,
-    //metadata: Metadata,
+    pub id: String,
+    pub status: String,
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    #[serde(default)]
+    pub audio_url: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub created_by: Option<String>,
+    #[serde(default)]
+    pub modified_at: Option<String>,
+    #[serde(default)]
+    pub started_at: Option<String>,
+    #[serde(default)]
+    pub completed_at: Option<String>,
+    #[serde(default)]
+    pub presenter_id: Option<String>,
+    #[serde(default)]
+    pub driver_id: Option<String>,
+    #[serde(default)]
+    pub config: Option<Config>,
+    #[serde(default)]
+    pub script: Option<ClipScript>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub webhook: Option<String>,
+    #[serde(default)]
+    pub result_url: Option<String>,
+    #[serde(default)]
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub metadata: Option<ClipMetadata>,
+    #[serde(default)]
+    pub error: Option<ClipError>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClipMetadata {
+    #[serde(default)]
+    pub driver_url: Option<String>,
+    #[serde(default)]
+    pub num_faces: Option<u32>,
+    #[serde(default)]
+    pub num_frames: Option<u32>,
+    #[serde(default)]
+    pub processing_fps: Option<f64>,
+    #[serde(default)]
+    pub resolution: Option<Vec<u32>>,
+    #[serde(default)]
+    pub size_kib: Option<f64>,
+}
+
+/// The script of a clip as echoed by the api, which describes it rather
+/// than repeating the request: text scripts come back without their input.
+/// Everything but the type and length is kept as sent in `other`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClipScript {
+    #[serde(default)]
+    pub r#type: Option<String>,
+    #[serde(default)]
+    pub length: Option<u64>,
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClipError {
+    #[serde(default)]
+    pub kind: String,
+    #[serde(default)]
+    pub description: String,
 }
 
 pub async fn get_clip(id: &str) -> Result<Clip> {
     let c = ClientBuilder::new()?
         .method(GET)?
-        .path(&format!("{}/{}", CLIPS_PATH, id))?
+        .path(format!("{}/{}", CLIPS_PATH, id))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let clip = serde_json::from_slice::<Clip>(&resp.as_ref())?;
+    let clip = serde_json::from_slice::<Clip>(resp.as_ref())?;
 
     Ok(clip)
 }
@@ -405,7 +657,7 @@ pub async fn get_clip(id: &str) -> Result<Clip> {
 pub async fn delete_clip(id: &str) -> Result<()> {
     let c = ClientBuilder::new()?
         .method(DELETE)?
-        .path(&format!("{}/{}", CLIPS_PATH, id))?
+        .path(format!("{}/{}", CLIPS_PATH, id))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
@@ -417,33 +669,139 @@ pub async fn delete_clip(id: &str) -> Result<()> {
 pub async fn get_presenter_drivers(presenter_id: &str) -> Result<GetPresenterDriversResponse> {
     let c = ClientBuilder::new()?
         .method(GET)?
-        .path(&format!("{}{}/{}{}", CLIPS_PATH, PRESENTERS_PATH, presenter_id, DRIVERS_PATH))?
+        .path(format!("{}{}/{}{}", CLIPS_PATH, PRESENTERS_PATH, presenter_id, DRIVERS_PATH))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let drivers = serde_json::from_slice::<GetPresenterDriversResponse>(&resp.as_ref())?;
+    let drivers = serde_json::from_slice::<GetPresenterDriversResponse>(resp.as_ref())?;
 
     Ok(drivers)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+fn check_driver(presenter_id: &str, driver_id: Option<&str>, drivers: &[ClipDriver]) -> Result<String> {
+    match driver_id {
+        Some(driver_id) if drivers.iter().any(|d| d.driver_id == driver_id) => Ok(driver_id.to_string()),
+        _ => Err(Box::new(RequestBodyBuildError::IncompatibleDriver {
+            presenter_id: presenter_id.to_string(),
+            driver_id: driver_id.unwrap_or_default().to_string(),
+            valid: drivers.iter().map(|d| d.driver_id.clone()).collect(),
+        })),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GetPresenterDriversResponse {
-    clips_drivers: Vec<ClipDriver>,
+    pub clips_drivers: Vec<ClipDriver>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClipDriver {
-    created_at: String,
-    driver_id: String,
-    driver_image_url: String,
-    gender: String,
-    modified_at: String,
-    name: String,
-    presenter_id: String,
-    preview_url: String,
-    thumbnail_url: String,
-    video_url: String,
+    pub created_at: String,
+    pub driver_id: String,
+    pub driver_image_url: String,
+    pub gender: String,
+    pub modified_at: String,
+    pub name: String,
+    pub presenter_id: String,
+    pub preview_url: String,
+    pub thumbnail_url: String,
+    pub video_url: String,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::support::fixtures::driver;
+
+    #[test]
+    fn clips_list_reports_malformed_entries() {
+        let body = serde_json::json!({
+            "clips": [
+                {
+                    "id": "clp_done",
+                    "owner_id": "google-oauth2|1234",
+                    "status": "done",
+                    "created_at": "2023-10-01T10:00:00.000Z",
+                    "created_by": "google-oauth2|1234",
+                    "started_at": "2023-10-01T10:00:01.000Z",
+                    "completed_at": "2023-10-01T10:00:20.000Z",
+                    "presenter_id": "amy-jcwCkr1grs",
+                    "driver_id": "uM00QMwJ9x",
+                    "audio_url": "https://example.com/clp_done.wav",
+                    "result_url": "https://example.com/clp_done.mp4",
+                    "duration": 4.2,
+                    "config": {"result_format": "webm"},
+                    "script": {
+                        "type": "text",
+                        "length": 39,
+                        "subtitles": "false",
+                        "provider": {"type": "microsoft", "voice_id": "en-US-JennyNeural"},
+                    },
+                    "metadata": {"num_frames": 105, "resolution": [512, 512]},
+                },
+                {"id": "clp_started", "status": "started"},
+                {"id": "clp_broken"},
+            ],
+        });
+
+        let resp = serde_json::from_value::<GetClipsResponse>(body).unwrap();
+
+        assert_eq!(resp.clips.len(), 2);
+        assert_eq!(resp.clips[0].duration, Some(4.2));
+        assert_eq!(
+            resp.clips[0].config.as_ref().and_then(|c| c.result_format),
+            Some(ResultFormat::Unknown)
+        );
+        let script = resp.clips[0].script.as_ref().unwrap();
+        assert_eq!(script.r#type.as_deref(), Some("text"));
+        assert_eq!(script.length, Some(39));
+        assert_eq!(script.other["provider"]["voice_id"], "en-US-JennyNeural");
+        assert_eq!(resp.clips[0].metadata.as_ref().unwrap().num_frames, Some(105));
+        assert!(resp.clips[1].result_url.is_none());
+        assert_eq!(resp.malformed.len(), 1);
+        assert_eq!(resp.malformed[0].id.as_deref(), Some("clp_broken"));
+    }
+
+    #[test]
+    fn background_and_crop_are_validated() {
+        let background = BackgroundBuilder::new().color("#00ff00").build().unwrap();
+        assert_eq!(serde_json::to_string(&background).unwrap(), r##"{"color":"#00ff00"}"##);
+        assert!(BackgroundBuilder::new().color("green").build().is_err());
+        assert!(BackgroundBuilder::new().build().is_err());
+
+        let config = PresenterConfigBuilder::new()
+            .rectangle(0.0, 0.25, 1.0, 0.75)
+            .build()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::json!({
+                "crop": {
+                    "type": "rectangle",
+                    "rectangle": {"bottom": 1.0, "left": 0.25, "right": 0.75, "top": 0.0},
+                },
+            })
+        );
+        assert!(PresenterConfigBuilder::new().rectangle(0.0, 0.0, 1.5, 1.0).build().is_err());
+        assert!(PresenterConfigBuilder::new().rectangle(0.0, 0.8, 1.0, 0.2).build().is_err());
+    }
+
+    #[test]
+    fn driver_is_checked_against_presenter_drivers() {
+        let drivers = vec![driver("amy-jcwCkr1grs", "uM00QMwJ9x"), driver("amy-jcwCkr1grs", "Vcq0R4a8F0")];
+
+        assert_eq!(
+            check_driver("amy-jcwCkr1grs", Some("Vcq0R4a8F0"), &drivers).unwrap(),
+            "Vcq0R4a8F0"
+        );
+
+        let err = check_driver("amy-jcwCkr1grs", Some("unknown"), &drivers).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "driver unknown is not available for presenter amy-jcwCkr1grs, valid drivers: uM00QMwJ9x, Vcq0R4a8F0"
+        );
+    }
+}
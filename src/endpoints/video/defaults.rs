@@ -0,0 +1,178 @@
+// File: defaults.rs
+// Path: src/endpoints/video/defaults.rs
+
+use super::*;
+use crate::endpoints::resources::settings::Settings;
+use rand::Rng;
+
+/// Values merged into talks, clips and animations when they are sent with
+/// `create_talk_with`, `create_with` or `create_animation_with`, so a service
+/// configures them once instead of on every builder.
+/// Values set on a request always win over these defaults, and every job
+/// sent gets a result url of its own under `result_url_prefix`.
+/// Animations take no persist flag or voice, so only the webhook, result url
+/// and config apply to them.
+#[derive(Debug, Clone, Default)]
+pub struct RequestDefaults {
+    webhook: Option<String>,
+    persist: Option<bool>,
+    result_url_prefix: Option<String>,
+    config: Option<Config>,
+    provider: Option<TTSProvider>,
+}
+
+impl RequestDefaults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The webhook, voice, logo and result format of the account settings.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            webhook: settings.webhook.clone(),
+            config: settings.config(),
+            provider: settings.tts.clone(),
+            ..Self::default()
+        }
+    }
+
+    pub fn webhook(mut self, webhook: &str) -> Self {
+        self.webhook = Some(webhook.to_string());
+        self
+    }
+
+    pub fn persist(mut self, persist: bool) -> Self {
+        self.persist = Some(persist);
+        self
+    }
+
+    /// Videos without a result url are uploaded under this prefix, each to a
+    /// file of its own.
+    pub fn result_url_prefix(mut self, result_url_prefix: &str) -> Self {
+        self.result_url_prefix = Some(result_url_prefix.to_string());
+        self
+    }
+
+    /// Merged field by field into the config of talks and clips.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Voice of text scripts that do not choose one.
+    pub fn provider(mut self, provider: TTSProvider) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// These defaults, with the values they leave unset taken from `fallback`,
+    /// such as the defaults `from_settings` of the account.
+    pub fn or(self, fallback: RequestDefaults) -> Self {
+        Self {
+            webhook: self.webhook.or(fallback.webhook),
+            persist: self.persist.or(fallback.persist),
+            result_url_prefix: self.result_url_prefix.or(fallback.result_url_prefix),
+            config: merge_config(self.config, fallback.config.as_ref()),
+            provider: self.provider.or(fallback.provider),
+        }
+    }
+
+    pub(crate) fn fill_webhook(&self, webhook: &mut String) {
+        if let (true, Some(default)) = (webhook.is_empty(), &self.webhook) {
+            default.clone_into(webhook);
+        }
+    }
+
+    pub(crate) fn fill_persist(&self, persist: &mut Option<bool>) {
+        *persist = persist.or(self.persist);
+    }
+
+    /// Gives an unset result url a unique file name under the prefix, with
+    /// the extension of the result format.
+    pub(crate) fn fill_result_url(&self, result_url: &mut String, config: Option<&Config>) {
+        let Some(prefix) = self.result_url_prefix.as_ref().filter(|_| result_url.is_empty()) else {
+            return;
+        };
+        let extension = match config.and_then(|c| c.result_format) {
+            Some(ResultFormat::Gif) => "gif",
+            Some(ResultFormat::Mov) => "mov",
+            _ => "mp4",
+        };
+        *result_url = format!("{}{:016x}.{}", prefix, rand::thread_rng().gen::<u64>(), extension);
+    }
+
+    pub(crate) fn fill_config(&self, config: &mut Option<Config>) {
+        *config = merge_config(config.take(), self.config.as_ref());
+    }
+
+    /// Sets the voice of a text script that does not choose one.
+    pub(crate) fn fill_provider(&self, script: &mut Script) {
+        if let (Some(default), Script::Text { provider: provider @ None, .. }) = (&self.provider, script) {
+            *provider = Some(default.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::animations::AnimationRequestBodyBuilder;
+
+    #[test]
+    fn defaults_only_fill_unset_values() {
+        let defaults = RequestDefaults::new()
+            .webhook("https://example.com/hook")
+            .persist(true)
+            .result_url_prefix("s3://bucket/videos/");
+
+        let mut webhook = String::new();
+        defaults.fill_webhook(&mut webhook);
+        assert_eq!(webhook, "https://example.com/hook");
+
+        let mut webhook = "https://example.com/other".to_string();
+        defaults.fill_webhook(&mut webhook);
+        assert_eq!(webhook, "https://example.com/other");
+
+        let mut persist = Some(false);
+        defaults.fill_persist(&mut persist);
+        assert_eq!(persist, Some(false));
+
+        let gif = ConfigBuilder::new().result_format(ResultFormat::Gif).build().unwrap();
+        let mut first = String::new();
+        defaults.fill_result_url(&mut first, Some(&gif));
+        let mut second = String::new();
+        defaults.fill_result_url(&mut second, Some(&gif));
+        assert!(first.starts_with("s3://bucket/videos/") && first.ends_with(".gif"));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn defaults_take_precedence_over_account_settings() {
+        let settings = serde_json::from_value::<Settings>(serde_json::json!({
+            "webhook": "https://example.com/account",
+            "result_format": "mov",
+        }))
+        .unwrap();
+        let defaults = RequestDefaults::new().webhook("https://example.com/hook");
+
+        let resolved = defaults.or(RequestDefaults::from_settings(&settings));
+
+        assert_eq!(resolved.webhook.as_deref(), Some("https://example.com/hook"));
+        assert_eq!(resolved.config.and_then(|c| c.result_format), Some(ResultFormat::Mov));
+    }
+    #[test]
+    fn animations_take_the_config_defaults() {
+        let defaults = RequestDefaults::new()
+            .result_url_prefix("s3://bucket/videos/")
+            .config(ConfigBuilder::new().result_format(ResultFormat::Gif).build().unwrap());
+
+        let animation = AnimationRequestBodyBuilder::new()
+            .source_url("www.dummyurl.com".to_string())
+            .build().unwrap()
+            .with_defaults(&defaults);
+        let animation = serde_json::to_value(&animation).unwrap();
+
+        assert_eq!(animation["config"]["result_format"], "gif");
+        assert!(animation["result_url"].as_str().unwrap().ends_with(".gif"));
+    }
+}
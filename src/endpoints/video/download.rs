@@ -0,0 +1,271 @@
+// File: download.rs
+// Path: src/endpoints/video/download.rs
+
+use super::*;
+use hyper::{header, StatusCode};
+use std::time::Duration;
+use tokio::{fs::OpenOptions, io::AsyncWrite};
+
+const RANGE: &str = "range";
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Progress of a running download, passed to the progress callback after every chunk.
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadProgress {
+    /// Bytes of the video held by the writer, including any resumed offset.
+    pub downloaded: u64,
+    /// Size of the whole video, when the server reports it.
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadReport {
+    /// Bytes written by this call.
+    pub bytes_written: u64,
+    /// Size of the whole video once the download completed.
+    pub total_bytes: u64,
+    /// Whether the download continued from previously written bytes.
+    pub resumed: bool,
+}
+
+/// Streams the `result_url` of a finished talk, clip or animation into `writer`.
+/// Pass the number of bytes the writer already holds as `offset` to resume an
+/// interrupted download with an HTTP Range request.
+pub async fn download<W, F>(
+    url: &str,
+    writer: &mut W,
+    offset: u64,
+    mut on_progress: F,
+) -> Result<DownloadReport>
+where
+    W: AsyncWrite + Unpin,
+    F: FnMut(DownloadProgress),
+{
+    // The result is served from storage outside of the api, so no credentials are sent.
+    let mut cb = ClientBuilder::default().method(GET)?.url(url)?;
+    if offset > 0 {
+        cb = cb.header(RANGE, &format!("bytes={}-", offset))?;
+    }
+    let c = cb.build()?;
+
+    let mut res = c.open_request(Empty::<Bytes>::new()).await?;
+
+    let content_length = header_value(res.headers(), header::CONTENT_LENGTH)
+        .and_then(|v| v.parse::<u64>().ok());
+    let content_range = header_value(res.headers(), header::CONTENT_RANGE);
+
+    // `skip` is the number of leading body bytes the writer already holds.
+    let (mut skip, total) = match res.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            let raw = content_range.unwrap_or_default();
+            let range = ContentRange::parse(&raw)
+                .ok_or_else(|| DownloadError::InvalidContentRange(raw.clone()))?;
+            match range.range {
+                Some((start, _)) if start == offset => (0, range.total),
+                _ => return Err(Box::new(DownloadError::InvalidContentRange(raw))),
+            }
+        }
+        // The server ignored the range, so the prefix we already have is discarded.
+        StatusCode::OK => (offset, content_length),
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+            let total = content_range
+                .as_deref()
+                .and_then(ContentRange::parse)
+                .and_then(|r| r.total);
+            if total == Some(offset) {
+                return Ok(DownloadReport {
+                    bytes_written: 0,
+                    total_bytes: offset,
+                    resumed: true,
+                });
+            }
+            return Err(Box::new(DownloadError::UnexpectedStatus(
+                StatusCode::RANGE_NOT_SATISFIABLE.as_u16(),
+            )));
+        }
+        status => return Err(Box::new(DownloadError::UnexpectedStatus(status.as_u16()))),
+    };
+
+    let resumed = offset > 0 && skip == 0;
+    let mut received = 0;
+    let mut written = 0;
+    while let Some(resulting_frame) = res.frame().await {
+        let frame = resulting_frame?;
+        let Some(chunk) = frame.data_ref() else {
+            continue;
+        };
+        received += chunk.len() as u64;
+
+        let discard = skip.min(chunk.len() as u64) as usize;
+        skip -= discard as u64;
+        if discard == chunk.len() {
+            continue;
+        }
+
+        writer.write_all(&chunk[discard..]).await?;
+        written += (chunk.len() - discard) as u64;
+        on_progress(DownloadProgress {
+            downloaded: offset + written,
+            total,
+        });
+    }
+    writer.flush().await?;
+
+    if let Some(expected) = content_length {
+        if received != expected {
+            return Err(Box::new(DownloadError::Incomplete { expected, received }));
+        }
+    }
+
+    let downloaded = offset + written;
+    if let Some(expected) = total {
+        if downloaded != expected {
+            return Err(Box::new(DownloadError::Incomplete {
+                expected,
+                received: downloaded,
+            }));
+        }
+    }
+
+    Ok(DownloadReport {
+        bytes_written: written,
+        total_bytes: downloaded,
+        resumed,
+    })
+}
+
+/// Downloads `url` into the file at `path`.
+/// A partially downloaded file is resumed rather than fetched again, and
+/// interrupted transfers are retried up to `max_retries` times.
+pub async fn download_to_file<F>(
+    url: &str,
+    path: &str,
+    max_retries: u32,
+    mut on_progress: F,
+) -> Result<DownloadReport>
+where
+    F: FnMut(DownloadProgress),
+{
+    let mut file = OpenOptions::new().create(true).append(true).open(path).await?;
+    let initial = file.metadata().await?.len();
+
+    let mut attempt = 0;
+    loop {
+        file.flush().await?;
+        let offset = file.metadata().await?.len();
+
+        match download(url, &mut file, offset, &mut on_progress).await {
+            Ok(report) => {
+                return Ok(DownloadReport {
+                    bytes_written: report.total_bytes.saturating_sub(initial),
+                    total_bytes: report.total_bytes,
+                    resumed: initial > 0 || attempt > 0,
+                })
+            }
+            Err(e) if attempt < max_retries && is_retryable(e.as_ref()) => {
+                tokio::time::sleep(Duration::from_millis(RETRY_BASE_DELAY_MS << attempt)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Only dropped connections, timeouts, truncated bodies and `429` or `5xx`
+/// responses are retried; bad urls, TLS failures and local io errors are not.
+fn is_retryable(e: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    if let Some(e) = e.downcast_ref::<DownloadError>() {
+        return match e {
+            DownloadError::UnexpectedStatus(status) => *status == 429 || *status >= 500,
+            DownloadError::InvalidContentRange(_) => false,
+            DownloadError::Incomplete { .. } => true,
+        };
+    }
+    if let Some(e) = e.downcast_ref::<hyper::Error>() {
+        return e.is_incomplete_message() || e.is_closed() || e.is_canceled() || e.is_timeout();
+    }
+    if let Some(e) = e.downcast_ref::<std::io::Error>() {
+        return matches!(
+            e.kind(),
+            std::io::ErrorKind::ConnectionRefused
+                | std::io::ErrorKind::ConnectionReset
+                | std::io::ErrorKind::ConnectionAborted
+                | std::io::ErrorKind::NotConnected
+                | std::io::ErrorKind::BrokenPipe
+                | std::io::ErrorKind::TimedOut
+                | std::io::ErrorKind::UnexpectedEof
+                | std::io::ErrorKind::Interrupted
+        );
+    }
+    false
+}
+
+fn header_value(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+/// A parsed `Content-Range: bytes <start>-<end>/<total>` header.
+#[derive(Debug, PartialEq)]
+struct ContentRange {
+    range: Option<(u64, u64)>,
+    total: Option<u64>,
+}
+
+impl ContentRange {
+    fn parse(value: &str) -> Option<Self> {
+        let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+
+        let range = match range {
+            "*" => None,
+            r => {
+                let (start, end) = r.split_once('-')?;
+                Some((start.parse().ok()?, end.parse().ok()?))
+            }
+        };
+
+        let total = match total {
+            "*" => None,
+            t => Some(t.parse().ok()?),
+        };
+
+        Some(Self { range, total })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_range_is_parsing() {
+        assert_eq!(
+            ContentRange::parse("bytes 100-199/200"),
+            Some(ContentRange { range: Some((100, 199)), total: Some(200) })
+        );
+        assert_eq!(
+            ContentRange::parse("bytes */200"),
+            Some(ContentRange { range: None, total: Some(200) })
+        );
+        assert_eq!(
+            ContentRange::parse("bytes 0-9/*"),
+            Some(ContentRange { range: Some((0, 9)), total: None })
+        );
+        assert_eq!(ContentRange::parse("items 0-9/10"), None);
+        assert_eq!(ContentRange::parse("bytes a-9/10"), None);
+    }
+
+    #[test]
+    fn only_transient_failures_are_retried() {
+        let io = |kind| std::io::Error::from(kind);
+
+        assert!(is_retryable(&DownloadError::UnexpectedStatus(503)));
+        assert!(is_retryable(&DownloadError::UnexpectedStatus(429)));
+        assert!(!is_retryable(&DownloadError::UnexpectedStatus(404)));
+        assert!(is_retryable(&io(std::io::ErrorKind::ConnectionReset)));
+        assert!(!is_retryable(&io(std::io::ErrorKind::PermissionDenied)));
+        assert!(!is_retryable(&"not a uri".parse::<Uri>().unwrap_err()));
+    }
+}
@@ -0,0 +1,236 @@
+// File: estimate.rs
+// Path: src/endpoints/video/estimate.rs
+
+use super::*;
+use super::animations::AnimationRequestBody;
+use super::clips::ClipRequestBody;
+use super::talks::TalkRequestBody;
+use crate::support::wav::read_wav_info;
+use std::collections::HashMap;
+
+/// Seconds of talk or clip video covered by one credit.
+pub const SECONDS_PER_CREDIT: f64 = 15.0;
+/// Credits charged for one animation, whatever its length.
+pub const CREDITS_PER_ANIMATION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostEstimate {
+    /// Predicted length of the video in seconds, unknown for animations.
+    pub duration: Option<f64>,
+    pub credits: u32,
+}
+
+/// Predicts the length and credit cost of talks, clips and animations before
+/// they are submitted.
+/// Text scripts are timed from their word count and the speaking rate of the
+/// voice; audio scripts need their duration registered up front, either
+/// directly or by parsing the local WAV file that was uploaded.
+#[derive(Debug, Clone)]
+pub struct CostEstimator {
+    seconds_per_credit: f64,
+    credits_per_animation: u32,
+    words_per_minute: f32,
+    voice_words_per_minute: HashMap<String, f32>,
+    audio_durations: HashMap<String, f64>,
+}
+
+impl Default for CostEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CostEstimator {
+    pub fn new() -> Self {
+        Self {
+            seconds_per_credit: SECONDS_PER_CREDIT,
+            credits_per_animation: CREDITS_PER_ANIMATION,
+            words_per_minute: DEFAULT_WORDS_PER_MINUTE,
+            voice_words_per_minute: HashMap::new(),
+            audio_durations: HashMap::new(),
+        }
+    }
+
+    pub fn seconds_per_credit(mut self, seconds_per_credit: f64) -> Self {
+        self.seconds_per_credit = seconds_per_credit;
+        self
+    }
+
+    pub fn credits_per_animation(mut self, credits_per_animation: u32) -> Self {
+        self.credits_per_animation = credits_per_animation;
+        self
+    }
+
+    /// Speaking rate used for voices without their own rate.
+    pub fn words_per_minute(mut self, words_per_minute: f32) -> Self {
+        self.words_per_minute = words_per_minute;
+        self
+    }
+
+    pub fn voice_words_per_minute(mut self, voice_id: &str, words_per_minute: f32) -> Self {
+        self.voice_words_per_minute
+            .insert(voice_id.to_string(), words_per_minute);
+        self
+    }
+
+    /// Registers the length in seconds of the audio at `audio_url`.
+    pub fn audio_duration(mut self, audio_url: &str, seconds: f64) -> Self {
+        self.audio_durations.insert(audio_url.to_string(), seconds);
+        self
+    }
+
+    /// Registers the length of the audio at `audio_url` by reading the header
+    /// of the local WAV file it was uploaded from.
+    pub fn audio_wav_file(self, audio_url: &str, path: &str) -> Result<Self> {
+        let mut file = std::fs::File::open(path)?;
+        let info = read_wav_info(&mut file)?;
+        Ok(self.audio_duration(audio_url, info.duration()))
+    }
+
+    pub fn estimate_talk(&self, body: &TalkRequestBody) -> Result<CostEstimate> {
+        self.estimate_script(body.script(), body.config())
+    }
+
+    pub fn estimate_clip(&self, body: &ClipRequestBody) -> Result<CostEstimate> {
+        self.estimate_script(body.script(), body.config())
+    }
+
+    pub fn estimate_animation(&self, _body: &AnimationRequestBody) -> Result<CostEstimate> {
+        Ok(CostEstimate {
+            duration: None,
+            credits: self.credits_per_animation,
+        })
+    }
+
+    /// Predicted length in seconds of the video rendered from `script`.
+    pub fn script_duration(&self, script: &Script, config: Option<&Config>) -> Result<f64> {
+        let spoken = match script {
+            Script::Text { provider, input, ssml, .. } => {
+                let words_per_minute = provider
+                    .as_ref()
+                    .and_then(|p| self.voice_words_per_minute.get(p.voice_id()))
+                    .copied()
+                    .unwrap_or(self.words_per_minute);
+                text_duration(input, *ssml, words_per_minute)
+            }
+            Script::Audio { audio_url, .. } => *self
+                .audio_durations
+                .get(audio_url)
+                .ok_or_else(|| EstimateError::UnknownAudioDuration(audio_url.clone()))?,
+        };
+        let padding = config.and_then(|c| c.pad_audio).unwrap_or_default() as f64;
+
+        Ok(spoken + padding)
+    }
+
+    fn estimate_script(&self, script: &Script, config: Option<&Config>) -> Result<CostEstimate> {
+        let duration = self.script_duration(script, config)?;
+        let credits = ((duration / self.seconds_per_credit).ceil() as u32).max(1);
+
+        Ok(CostEstimate {
+            duration: Some(duration),
+            credits,
+        })
+    }
+}
+
+/// Seconds needed to speak `input`, including any SSML `<break time="..."/>` pauses.
+fn text_duration(input: &str, ssml: bool, words_per_minute: f32) -> f64 {
+    let mut words = 0;
+    let mut pauses = 0.0;
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let (text, tag) = match rest.find('<').filter(|_| ssml) {
+            Some(start) => {
+                let end = rest[start..].find('>').map_or(rest.len(), |e| start + e + 1);
+                let tag = &rest[start..end];
+                let text = &rest[..start];
+                rest = &rest[end..];
+                (text, Some(tag))
+            }
+            None => {
+                let text = rest;
+                rest = "";
+                (text, None)
+            }
+        };
+
+        words += text.split_whitespace().count();
+        if let Some(tag) = tag.filter(|t| t.starts_with("<break")) {
+            pauses += break_duration(tag);
+        }
+    }
+
+    words as f64 * 60.0 / words_per_minute as f64 + pauses
+}
+
+fn break_duration(tag: &str) -> f64 {
+    let Some(start) = tag.find("time=") else {
+        return 0.0;
+    };
+    let value = tag[start + 5..]
+        .trim_start_matches(['"', '\''])
+        .split(['"', '\''])
+        .next()
+        .unwrap_or_default();
+
+    if let Some(ms) = value.strip_suffix("ms") {
+        ms.trim().parse::<f64>().map_or(0.0, |ms| ms / 1000.0)
+    } else if let Some(s) = value.strip_suffix('s') {
+        s.trim().parse::<f64>().unwrap_or_default()
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::talks::TalkRequestBodyBuilder;
+
+    #[test]
+    fn text_talk_is_estimated_from_words_per_minute() {
+        let input = vec!["word"; 100].join(" ");
+        let body = TalkRequestBodyBuilder::with_text_script()
+            .source_url("www.dummyurl.com").unwrap()
+            .input(&input).unwrap()
+            .provider(TTSProvider::microsoft("en-US-JennyNeural", None)).unwrap()
+            .build().unwrap();
+
+        let estimate = CostEstimator::new()
+            .voice_words_per_minute("en-US-JennyNeural", 120.0)
+            .estimate_talk(&body)
+            .unwrap();
+
+        assert_eq!(estimate, CostEstimate { duration: Some(50.0), credits: 4 });
+    }
+
+    #[test]
+    fn ssml_breaks_are_added_to_the_duration() {
+        let got = text_duration(
+            r#"<speak>one two<break time="1500ms"/>three<break time="2s"/></speak>"#,
+            true,
+            60.0,
+        );
+
+        assert_eq!(got, 6.5);
+    }
+
+    #[test]
+    fn audio_talk_needs_a_known_duration() {
+        let body = TalkRequestBodyBuilder::with_audio_script()
+            .source_url("www.dummyurl.com").unwrap()
+            .audio_url("www.dummyaudiourl.com").unwrap()
+            .build().unwrap();
+
+        assert!(CostEstimator::new().estimate_talk(&body).is_err());
+
+        let estimate = CostEstimator::new()
+            .audio_duration("www.dummyaudiourl.com", 15.5)
+            .estimate_talk(&body)
+            .unwrap();
+
+        assert_eq!(estimate.credits, 2);
+    }
+}
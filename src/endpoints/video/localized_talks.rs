@@ -0,0 +1,52 @@
+// File: localized_talks.rs
+// Path: src/endpoints/video/localized_talks.rs
+
+use super::*;
+use super::talks::CreateTalkResponse;
+use std::collections::BTreeMap;
+
+/// The text of a talk in one locale.
+#[derive(Debug, Clone)]
+pub struct Translation {
+    pub input: String,
+    /// Replaces the voice of the base talk, see `VoiceResolver`.
+    pub provider: Option<TTSProvider>,
+}
+
+impl Translation {
+    pub fn new(input: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            provider: None,
+        }
+    }
+
+    pub fn provider(mut self, provider: TTSProvider) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+}
+
+/// The talk created for every locale, or why it could not be created.
+#[derive(Debug)]
+pub struct LocalizedTalks {
+    pub results: BTreeMap<String, Result<CreateTalkResponse>>,
+}
+
+impl LocalizedTalks {
+    pub fn succeeded(&self) -> impl Iterator<Item = (&str, &CreateTalkResponse)> {
+        self.results
+            .iter()
+            .filter_map(|(locale, r)| Some((locale.as_str(), r.as_ref().ok()?)))
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &(dyn std::error::Error + Send + Sync + 'static))> {
+        self.results
+            .iter()
+            .filter_map(|(locale, r)| Some((locale.as_str(), r.as_ref().err()?.as_ref())))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.results.values().all(|r| r.is_ok())
+    }
+}
@@ -2,18 +2,25 @@ pub mod talks;
 pub mod clips;
 pub mod animations;
 pub mod streams;
+pub mod download;
+pub mod talk_groups;
+pub mod localized_talks;
+pub mod estimate;
+pub mod presenters;
+pub mod defaults;
 
 pub use crate::client::*;
 pub use crate::prelude::*;
 pub use crate::error::*;
 pub use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum Script {
      Text {
         r#type: String,
         subtitles: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
         provider: Option<TTSProvider>,
         input: String,
         ssml: bool,
@@ -28,13 +35,14 @@ pub enum Script {
 
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum TTSProvider {
     MicrosoftTTS {
         r#type: String,
         voice_id: String,
-        //voice_config: VoiceConfig,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        voice_config: Option<VoiceConfig>,
     },
     ElevenLabsTTS {
         r#type: String,
@@ -54,80 +62,549 @@ pub enum TTSProvider {
 
 }
 
+/// Speaking style of Microsoft voices.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct VoiceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pitch: Option<String>,
+}
+
+impl TTSProvider {
+    pub fn microsoft(voice_id: &str, style: Option<&str>) -> Self {
+        TTSProvider::MicrosoftTTS {
+            r#type: "microsoft".to_string(),
+            voice_id: voice_id.to_string(),
+            voice_config: style.map(|style| VoiceConfig {
+                style: Some(style.to_string()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn eleven_labs(voice_id: &str) -> Self {
+        TTSProvider::ElevenLabsTTS {
+            r#type: "elevenlabs".to_string(),
+            voice_id: voice_id.to_string(),
+        }
+    }
+
+    pub fn amazon(voice_id: &str) -> Self {
+        TTSProvider::AmazonTTS {
+            r#type: "amazon".to_string(),
+            voice_id: voice_id.to_string(),
+        }
+    }
+
+    pub fn afflorithmics(voice_id: &str) -> Self {
+        TTSProvider::AfflorithmicsTTS {
+            r#type: "afflorithmics".to_string(),
+            voice_id: voice_id.to_string(),
+        }
+    }
+
+    pub fn voice_id(&self) -> &str {
+        match self {
+            TTSProvider::MicrosoftTTS { voice_id, .. }
+            | TTSProvider::ElevenLabsTTS { voice_id, .. }
+            | TTSProvider::AmazonTTS { voice_id, .. }
+            | TTSProvider::AfflorithmicsTTS { voice_id, .. } => voice_id,
+        }
+    }
+}
+
 
 
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A driver of the D-ID bank, written as its `bank://` url on the wire.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Driver {
-    #[serde(rename = "bank://lively/driver-01")]
     LivelyDriver01,
-    #[serde(rename = "bank://lively/driver-02")]
     LivelyDriver02,
-    #[serde(rename = "bank://lively/driver-03")]
     LivelyDriver03,
-    #[serde(rename = "bank://lively/driver-04")]
     LivelyDriver04,
-    #[serde(rename = "bank://lively/driver-05")]
     LivelyDriver05,
-    #[serde(rename = "bank://lively/driver-06")]
     LivelyDriver06,
-    #[serde(rename = "bank://subtle/driver-01")]
     SubtleDriver01,
-    #[serde(rename = "bank://subtle/driver-02")]
     SubtleDriver02,
-    #[serde(rename = "bank://subtle/driver-03")]
     SubtleDriver03,
-    #[serde(rename = "bank://subtle/driver-04")]
     SubtleDriver04,
+    Lively,
+    Subtle,
+    Natural,
+    Fun,
+    Dance,
+    Nostalgia,
+    Classics,
+}
+
+impl Driver {
+    pub const ALL: [Driver; 17] = [
+        Driver::LivelyDriver01,
+        Driver::LivelyDriver02,
+        Driver::LivelyDriver03,
+        Driver::LivelyDriver04,
+        Driver::LivelyDriver05,
+        Driver::LivelyDriver06,
+        Driver::SubtleDriver01,
+        Driver::SubtleDriver02,
+        Driver::SubtleDriver03,
+        Driver::SubtleDriver04,
+        Driver::Lively,
+        Driver::Subtle,
+        Driver::Natural,
+        Driver::Fun,
+        Driver::Dance,
+        Driver::Nostalgia,
+        Driver::Classics,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Driver::LivelyDriver01 => "bank://lively/driver-01",
+            Driver::LivelyDriver02 => "bank://lively/driver-02",
+            Driver::LivelyDriver03 => "bank://lively/driver-03",
+            Driver::LivelyDriver04 => "bank://lively/driver-04",
+            Driver::LivelyDriver05 => "bank://lively/driver-05",
+            Driver::LivelyDriver06 => "bank://lively/driver-06",
+            Driver::SubtleDriver01 => "bank://subtle/driver-01",
+            Driver::SubtleDriver02 => "bank://subtle/driver-02",
+            Driver::SubtleDriver03 => "bank://subtle/driver-03",
+            Driver::SubtleDriver04 => "bank://subtle/driver-04",
+            Driver::Lively => "bank://lively",
+            Driver::Subtle => "bank://subtle",
+            Driver::Natural => "bank://natural",
+            Driver::Fun => "bank://fun",
+            Driver::Dance => "bank://dance",
+            Driver::Nostalgia => "bank://nostalgia",
+            Driver::Classics => "bank://classics",
+        }
+    }
+
+}
+
+impl std::str::FromStr for Driver {
+    type Err = RequestBodyBuildError;
+
+    fn from_str(url: &str) -> std::result::Result<Self, Self::Err> {
+        Driver::ALL
+            .into_iter()
+            .find(|d| d.as_str() == url)
+            .ok_or_else(|| RequestBodyBuildError::UnknownDriver(url.to_string()))
+    }
+}
+
+impl Serialize for Driver {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Driver {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let url = String::deserialize(deserializer)?;
+        url.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The `driver_url` of talks, animations and streams.
+/// Urls that are neither a known bank driver nor a custom video are kept as
+/// `Other` so they serialize back unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DriverUrl {
+    Bank(Driver),
+    /// A driver video hosted at an `s3://` or `https://` url.
+    Custom(String),
+    Other(String),
+}
+
+impl DriverUrl {
+    pub fn custom(url: &str) -> Result<Self> {
+        if url.starts_with("s3://") || url.starts_with("https://") {
+            Ok(DriverUrl::Custom(url.to_string()))
+        } else {
+            Err(Box::new(RequestBodyBuildError::InvalidDriverUrl(url.to_string())))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            DriverUrl::Bank(driver) => driver.as_str(),
+            DriverUrl::Custom(url) | DriverUrl::Other(url) => url,
+        }
+    }
+}
+
+impl From<Driver> for DriverUrl {
+    fn from(driver: Driver) -> Self {
+        DriverUrl::Bank(driver)
+    }
 }
 
+impl From<&str> for DriverUrl {
+    fn from(url: &str) -> Self {
+        if let Ok(driver) = url.parse::<Driver>() {
+            return DriverUrl::Bank(driver);
+        }
+        DriverUrl::custom(url).unwrap_or_else(|_| DriverUrl::Other(url.to_string()))
+    }
+}
+
+impl From<String> for DriverUrl {
+    fn from(url: String) -> Self {
+        DriverUrl::from(url.as_str())
+    }
+}
 
+impl Serialize for DriverUrl {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
 
-#[derive(Serialize, Deserialize, Debug)]
+impl<'de> Deserialize<'de> for DriverUrl {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let url = String::deserialize(deserializer)?;
+        Ok(DriverUrl::from(url))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Config {
-    logo: Logo,
-    align_driver: bool,
-    align_expand_factor: f32,
-    auto_match: bool,
-    motion_factor: f32,
-    normalization_factor: f32,
-    sharpen: bool,
-    stitch: bool,
-    result_format: String,
-    fluent: bool,
-    pad_audio: f32,
-    driver_expression: DriverExpressions,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logo: Option<Logo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    align_driver: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    align_expand_factor: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auto_match: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    motion_factor: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    normalization_factor: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sharpen: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stitch: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result_format: Option<ResultFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fluent: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pad_audio: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    driver_expressions: Option<DriverExpressions>,
+}
+
+impl Config {
+    /// Fills every field not set on this config from `defaults`.
+    pub fn merge_defaults(&mut self, defaults: &Config) {
+        let d = defaults.clone();
+        self.logo = self.logo.take().or(d.logo);
+        self.align_driver = self.align_driver.or(d.align_driver);
+        self.align_expand_factor = self.align_expand_factor.or(d.align_expand_factor);
+        self.auto_match = self.auto_match.or(d.auto_match);
+        self.motion_factor = self.motion_factor.or(d.motion_factor);
+        self.normalization_factor = self.normalization_factor.or(d.normalization_factor);
+        self.sharpen = self.sharpen.or(d.sharpen);
+        self.stitch = self.stitch.or(d.stitch);
+        self.result_format = self.result_format.or(d.result_format);
+        self.fluent = self.fluent.or(d.fluent);
+        self.pad_audio = self.pad_audio.or(d.pad_audio);
+        self.driver_expressions = self.driver_expressions.take().or(d.driver_expressions);
+    }
+}
+
+/// `config` with its unset fields taken from `defaults`.
+pub(crate) fn merge_config(config: Option<Config>, defaults: Option<&Config>) -> Option<Config> {
+    match (config, defaults) {
+        (Some(mut config), Some(defaults)) => {
+            config.merge_defaults(defaults);
+            Some(config)
+        }
+        (config, defaults) => config.or_else(|| defaults.cloned()),
+    }
+}
+
+/// `result_url` with `suffix` appended to its file name, before the extension
+/// and any query string, so talks derived from one request do not overwrite
+/// each other. An unset url stays unset.
+pub(crate) fn suffix_result_url(result_url: &str, suffix: &str) -> String {
+    if result_url.is_empty() {
+        return String::new();
+    }
+    let (path, query) = result_url.split_at(result_url.find('?').unwrap_or(result_url.len()));
+    let name_start = path.rfind('/').map_or(0, |i| i + 1);
+    let stem_end = path[name_start..].rfind('.').map_or(path.len(), |i| name_start + i);
+    format!("{}{}{}{}", &path[..stem_end], suffix, &path[stem_end..], query)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultFormat {
+    Mp4,
+    Gif,
+    Mov,
+    /// A format returned by the api that this crate does not know yet.
+    /// Not meant to be sent.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Logo {
     url: String,
     position: Vec<u32>
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Logo {
+    /// `x` and `y` are the pixel offsets of the logo from the top left of the video.
+    pub fn new(url: &str, x: u32, y: u32) -> Self {
+        Self {
+            url: url.to_string(),
+            position: vec![x, y],
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn logo(mut self, logo: Logo) -> Self {
+        self.config.logo = Some(logo);
+        self
+    }
+
+    pub fn align_driver(mut self, align_driver: bool) -> Self {
+        self.config.align_driver = Some(align_driver);
+        self
+    }
+
+    /// Relative expansion of the face area, between 0 and 1.
+    pub fn align_expand_factor(mut self, align_expand_factor: f32) -> Self {
+        self.config.align_expand_factor = Some(align_expand_factor);
+        self
+    }
+
+    pub fn auto_match(mut self, auto_match: bool) -> Self {
+        self.config.auto_match = Some(auto_match);
+        self
+    }
+
+    /// Scale of the head motion, between 0 and 1.
+    pub fn motion_factor(mut self, motion_factor: f32) -> Self {
+        self.config.motion_factor = Some(motion_factor);
+        self
+    }
+
+    pub fn normalization_factor(mut self, normalization_factor: f32) -> Self {
+        self.config.normalization_factor = Some(normalization_factor);
+        self
+    }
+
+    pub fn sharpen(mut self, sharpen: bool) -> Self {
+        self.config.sharpen = Some(sharpen);
+        self
+    }
+
+    pub fn stitch(mut self, stitch: bool) -> Self {
+        self.config.stitch = Some(stitch);
+        self
+    }
+
+    pub fn result_format(mut self, result_format: ResultFormat) -> Self {
+        self.config.result_format = Some(result_format);
+        self
+    }
+
+    pub fn fluent(mut self, fluent: bool) -> Self {
+        self.config.fluent = Some(fluent);
+        self
+    }
+
+    /// Seconds of silence added to the start and end of the audio, between 0 and 60.
+    pub fn pad_audio(mut self, pad_audio: f32) -> Self {
+        self.config.pad_audio = Some(pad_audio);
+        self
+    }
+
+    pub fn driver_expressions(mut self, driver_expressions: DriverExpressions) -> Self {
+        self.config.driver_expressions = Some(driver_expressions);
+        self
+    }
+
+    pub fn build(self) -> Result<Config> {
+        check_range("align_expand_factor", self.config.align_expand_factor, 0.0, 1.0)?;
+        check_range("motion_factor", self.config.motion_factor, 0.0, 1.0)?;
+        check_range("pad_audio", self.config.pad_audio, 0.0, 60.0)?;
+
+        Ok(self.config)
+    }
+}
+
+fn check_range(field: &'static str, value: Option<f32>, min: f32, max: f32) -> Result<()> {
+    match value {
+        Some(value) if !(min..=max).contains(&value) => Err(Box::new(
+            RequestBodyBuildError::OutOfRange { field, min, max, value },
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DriverExpressions {
-    expressions: Vec<ExpressionObject>
+    expressions: Vec<ExpressionObject>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transition_frames: Option<u32>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ExpressionObject {
-    start_frame: f32,
-    expression: String,
+    start_frame: u32,
+    expression: Expression,
     intensity: f32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Expression {
+    Neutral,
+    Happy,
+    Serious,
+    Surprise,
+}
+
+/// D-ID renders videos at 25 frames per second.
+pub const DEFAULT_FRAME_RATE: f32 = 25.0;
+/// Average speaking rate used to place expressions on script words.
+pub const DEFAULT_WORDS_PER_MINUTE: f32 = 150.0;
+
+#[derive(Debug)]
+enum Cue {
+    Seconds(f32),
+    Word(usize),
+}
+
+/// Builds `driver_expressions` from a timeline of seconds or script word offsets,
+/// converting each cue to a frame of the output video.
+#[derive(Debug)]
+pub struct DriverExpressionsBuilder {
+    frame_rate: f32,
+    words_per_minute: f32,
+    transition_frames: Option<u32>,
+    cues: Vec<(Cue, Expression, f32)>,
+}
+
+impl Default for DriverExpressionsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DriverExpressionsBuilder {
+    pub fn new() -> Self {
+        Self {
+            frame_rate: DEFAULT_FRAME_RATE,
+            words_per_minute: DEFAULT_WORDS_PER_MINUTE,
+            transition_frames: None,
+            cues: Vec::new(),
+        }
+    }
+
+    /// Frames per second of the output video, between 1 and 120.
+    pub fn frame_rate(mut self, frame_rate: f32) -> Self {
+        self.frame_rate = frame_rate;
+        self
+    }
+
+    /// Speaking rate used to convert word offsets passed to `at_word` into time,
+    /// between 1 and 1000.
+    pub fn words_per_minute(mut self, words_per_minute: f32) -> Self {
+        self.words_per_minute = words_per_minute;
+        self
+    }
+
+    pub fn transition_frames(mut self, transition_frames: u32) -> Self {
+        self.transition_frames = Some(transition_frames);
+        self
+    }
+
+    /// Starts `expression` at `seconds` into the video, with an intensity between 0 and 1.
+    pub fn at_seconds(mut self, seconds: f32, expression: Expression, intensity: f32) -> Self {
+        self.cues.push((Cue::Seconds(seconds), expression, intensity));
+        self
+    }
+
+    /// Starts `expression` at the zero based `word` of the script, with an intensity between 0 and 1.
+    pub fn at_word(mut self, word: usize, expression: Expression, intensity: f32) -> Self {
+        self.cues.push((Cue::Word(word), expression, intensity));
+        self
+    }
+
+    pub fn build(self) -> Result<DriverExpressions> {
+        check_range("frame_rate", Some(self.frame_rate), 1.0, 120.0)?;
+        check_range("words_per_minute", Some(self.words_per_minute), 1.0, 1000.0)?;
+
+        let mut expressions: Vec<ExpressionObject> = Vec::with_capacity(self.cues.len());
+
+        for (cue, expression, intensity) in self.cues {
+            check_range("intensity", Some(intensity), 0.0, 1.0)?;
+
+            let seconds = match cue {
+                Cue::Seconds(seconds) => seconds,
+                Cue::Word(word) => word as f32 * 60.0 / self.words_per_minute,
+            };
+            if !seconds.is_finite() || seconds < 0.0 {
+                return Err(Box::new(RequestBodyBuildError::InvalidTimestamp(seconds)));
+            }
+            let start_frame = (seconds * self.frame_rate).round() as u32;
+
+            if let Some(previous) = expressions.last() {
+                if start_frame <= previous.start_frame {
+                    return Err(Box::new(RequestBodyBuildError::ExpressionsOutOfOrder {
+                        previous: previous.start_frame,
+                        next: start_frame,
+                    }));
+                }
+            }
+
+            expressions.push(ExpressionObject {
+                start_frame,
+                expression,
+                intensity,
+            });
+        }
+
+        Ok(DriverExpressions {
+            expressions,
+            transition_frames: self.transition_frames,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Face {
-    size: u32,
-    top_left: Vec<u32>,
-    overlap: Overlap,
-    face_id: String,
-    detection_confidence: f32,
-    detection: Detection,
+    pub size: u32,
+    pub top_left: Vec<u32>,
+    pub overlap: Overlap,
+    pub face_id: String,
+    #[serde(alias = "detect_confidence")]
+    pub detection_confidence: f32,
+    pub detection: Detection,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Overlap {
     No,
@@ -136,10 +613,139 @@ pub enum Overlap {
     Unknown,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Detection {
-    top: f32,
-    left: f32,
-    bottom: f32,
-    right: f32,
+    pub top: f32,
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+}
+
+impl Detection {
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.bottom - self.top
+    }
+
+    pub fn area(&self) -> f32 {
+        self.width().max(0.0) * self.height().max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_builder_skips_unset_fields() {
+        let config = ConfigBuilder::new()
+            .stitch(true)
+            .result_format(ResultFormat::Mov)
+            .build()
+            .unwrap();
+
+        let got = serde_json::to_string(&config).unwrap();
+
+        assert_eq!(got, r#"{"stitch":true,"result_format":"mov"}"#);
+    }
+
+    #[test]
+    fn driver_expressions_builder_converts_cues_to_frames() {
+        let expressions = DriverExpressionsBuilder::new()
+            .at_seconds(0.0, Expression::Neutral, 1.0)
+            .at_seconds(2.0, Expression::Happy, 0.5)
+            .at_word(15, Expression::Surprise, 1.0)
+            .transition_frames(10)
+            .build()
+            .unwrap();
+
+        let got = serde_json::to_value(&expressions).unwrap();
+
+        assert_eq!(
+            got,
+            serde_json::json!({
+                "expressions": [
+                    {"start_frame": 0, "expression": "neutral", "intensity": 1.0},
+                    {"start_frame": 50, "expression": "happy", "intensity": 0.5},
+                    {"start_frame": 150, "expression": "surprise", "intensity": 1.0},
+                ],
+                "transition_frames": 10,
+            })
+        );
+    }
+
+    #[test]
+    fn driver_expressions_builder_rejects_unordered_cues_and_bad_intensity() {
+        assert!(DriverExpressionsBuilder::new()
+            .at_seconds(2.0, Expression::Happy, 1.0)
+            .at_seconds(1.0, Expression::Serious, 1.0)
+            .build()
+            .is_err());
+        assert!(DriverExpressionsBuilder::new()
+            .at_seconds(0.0, Expression::Happy, 1.5)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn driver_expressions_builder_rejects_bad_rates() {
+        for frame_rate in [0.0, -25.0, f32::NAN] {
+            let err = DriverExpressionsBuilder::new()
+                .frame_rate(frame_rate)
+                .at_seconds(0.0, Expression::Happy, 1.0)
+                .build()
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<RequestBodyBuildError>(),
+                Some(RequestBodyBuildError::OutOfRange { field: "frame_rate", .. })
+            ));
+        }
+        assert!(DriverExpressionsBuilder::new()
+            .words_per_minute(0.0)
+            .at_word(3, Expression::Happy, 1.0)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn driver_url_round_trips() {
+        for url in [
+            "bank://lively/driver-01",
+            "bank://natural",
+            "s3://d-id-drivers/custom.mp4",
+            "https://example.com/driver.mp4",
+            "bank://someday/driver-99",
+        ] {
+            let driver_url: DriverUrl = serde_json::from_value(serde_json::json!(url)).unwrap();
+            assert_eq!(serde_json::to_value(&driver_url).unwrap(), serde_json::json!(url));
+        }
+
+        assert_eq!(DriverUrl::from("bank://fun"), DriverUrl::Bank(Driver::Fun));
+        assert_eq!(
+            DriverUrl::from("bank://someday/driver-99"),
+            DriverUrl::Other("bank://someday/driver-99".to_string())
+        );
+        assert!(DriverUrl::custom("ftp://example.com/driver.mp4").is_err());
+    }
+
+    #[test]
+    fn bank_drivers_serialize_as_their_url() {
+        for driver in Driver::ALL {
+            let got = serde_json::to_value(driver).unwrap();
+            assert_eq!(got, serde_json::json!(driver.as_str()));
+            assert_eq!(serde_json::from_value::<Driver>(got).unwrap(), driver);
+        }
+        assert!(serde_json::from_value::<Driver>(serde_json::json!("bank://someday")).is_err());
+    }
+
+    #[test]
+    fn config_builder_rejects_out_of_range_values() {
+        assert!(ConfigBuilder::new().pad_audio(61.0).build().is_err());
+        assert!(ConfigBuilder::new().motion_factor(-0.1).build().is_err());
+        assert!(ConfigBuilder::new().align_expand_factor(1.5).build().is_err());
+        assert!(ConfigBuilder::new().pad_audio(60.0).build().is_ok());
+    }
 }
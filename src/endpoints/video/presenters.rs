@@ -0,0 +1,218 @@
+// File: presenters.rs
+// Path: src/endpoints/video/presenters.rs
+
+use super::*;
+use super::clips::{get_presenter_drivers, get_presenters, ClipDriver, Presenter};
+use std::collections::HashMap;
+use crate::support::cache::TtlCache;
+pub use crate::support::cache::DEFAULT_CATALOG_TTL;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ownership {
+    /// Presenters provided by D-ID.
+    Premium,
+    /// Presenters created by the account.
+    Custom,
+    /// The catalog was built without `account_owner_id`, so premium and
+    /// custom presenters cannot be told apart.
+    Unknown,
+}
+
+/// Filters for `PresenterCatalog::query`. Unset filters match every presenter.
+#[derive(Debug, Clone, Default)]
+pub struct PresenterQuery {
+    gender: Option<String>,
+    ownership: Option<Ownership>,
+    driver_ids: Vec<String>,
+}
+
+impl PresenterQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gender(mut self, gender: &str) -> Self {
+        self.gender = Some(gender.to_string());
+        self
+    }
+
+    pub fn ownership(mut self, ownership: Ownership) -> Self {
+        self.ownership = Some(ownership);
+        self
+    }
+
+    /// Only matches presenters having a driver with this id, may be repeated.
+    pub fn driver_id(mut self, driver_id: &str) -> Self {
+        self.driver_ids.push(driver_id.to_string());
+        self
+    }
+}
+
+/// Presenters and their drivers, loaded on first use and kept for `ttl` so
+/// repeated lookups do not hit the api.
+#[derive(Debug)]
+pub struct PresenterCatalog {
+    ttl: Duration,
+    account_owner_id: Option<String>,
+    presenters: TtlCache<Vec<Presenter>>,
+    drivers: HashMap<String, TtlCache<Vec<ClipDriver>>>,
+}
+
+impl Default for PresenterCatalog {
+    fn default() -> Self {
+        Self::new(DEFAULT_CATALOG_TTL)
+    }
+}
+
+impl PresenterCatalog {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            account_owner_id: None,
+            presenters: TtlCache::new(ttl),
+            drivers: HashMap::new(),
+        }
+    }
+
+    /// Presenters owned by `owner_id` are reported as `Ownership::Custom`,
+    /// every other presenter as `Ownership::Premium`.
+    /// Without it every presenter is `Ownership::Unknown`.
+    pub fn account_owner_id(mut self, owner_id: &str) -> Self {
+        self.account_owner_id = Some(owner_id.to_string());
+        self
+    }
+
+    /// Drops everything cached, so the next lookup reloads from the api.
+    pub fn invalidate(&mut self) {
+        self.presenters.invalidate();
+        self.drivers.clear();
+    }
+
+    pub async fn presenters(&mut self) -> Result<&[Presenter]> {
+        let presenters = self
+            .presenters
+            .get_or_load(|| async { Ok(get_presenters().await?.presenters) })
+            .await?;
+        Ok(presenters)
+    }
+
+    pub async fn presenter(&mut self, presenter_id: &str) -> Result<Option<Presenter>> {
+        Ok(self
+            .presenters()
+            .await?
+            .iter()
+            .find(|p| p.presenter_id == presenter_id)
+            .cloned())
+    }
+
+    pub async fn drivers(&mut self, presenter_id: &str) -> Result<&[ClipDriver]> {
+        let ttl = self.ttl;
+        let drivers = self
+            .drivers
+            .entry(presenter_id.to_string())
+            .or_insert_with(|| TtlCache::new(ttl))
+            .get_or_load(|| async { Ok(get_presenter_drivers(presenter_id).await?.clips_drivers) })
+            .await?;
+        Ok(drivers)
+    }
+
+    pub fn ownership(&self, presenter: &Presenter) -> Ownership {
+        match &self.account_owner_id {
+            Some(owner_id) if *owner_id == presenter.owner_id => Ownership::Custom,
+            Some(_) => Ownership::Premium,
+            None => Ownership::Unknown,
+        }
+    }
+
+    /// Presenters matching every filter of `query`.
+    /// Drivers are only loaded when the query filters on them.
+    pub async fn query(&mut self, query: &PresenterQuery) -> Result<Vec<Presenter>> {
+        let candidates = self
+            .presenters()
+            .await?
+            .iter()
+            .filter(|p| {
+                query
+                    .gender
+                    .as_ref()
+                    .is_none_or(|g| g.eq_ignore_ascii_case(&p.gender))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut matches = Vec::new();
+        for presenter in candidates {
+            if query.ownership.is_some_and(|o| o != self.ownership(&presenter)) {
+                continue;
+            }
+
+            if !query.driver_ids.is_empty() {
+                let drivers = self.drivers(&presenter.presenter_id).await?;
+                let has_drivers = query
+                    .driver_ids
+                    .iter()
+                    .all(|id| drivers.iter().any(|d| d.driver_id == *id));
+                if !has_drivers {
+                    continue;
+                }
+            }
+
+            matches.push(presenter);
+        }
+
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::support::fixtures::{driver, presenter};
+
+    fn catalog() -> PresenterCatalog {
+        let mut catalog = PresenterCatalog::default().account_owner_id("me");
+        catalog.presenters.insert(vec![
+            presenter("amy", "female", "d-id"),
+            presenter("bob", "male", "d-id"),
+            presenter("cat", "female", "me"),
+        ]);
+        for (presenter_id, drivers) in [
+            ("amy", vec![driver("amy", "d1")]),
+            ("cat", vec![driver("cat", "d1"), driver("cat", "d2")]),
+        ] {
+            let mut cache = TtlCache::new(catalog.ttl);
+            cache.insert(drivers);
+            catalog.drivers.insert(presenter_id.to_string(), cache);
+        }
+        catalog
+    }
+
+    #[tokio::test]
+    async fn catalog_queries_are_served_from_the_cache() {
+        let mut catalog = catalog();
+
+        let ids = |presenters: Vec<Presenter>| {
+            presenters.into_iter().map(|p| p.presenter_id).collect::<Vec<_>>()
+        };
+
+        let female = catalog.query(&PresenterQuery::new().gender("Female")).await.unwrap();
+        assert_eq!(ids(female), vec!["amy", "cat"]);
+
+        let custom = catalog.query(&PresenterQuery::new().ownership(Ownership::Custom)).await.unwrap();
+        assert_eq!(ids(custom), vec!["cat"]);
+
+        let with_driver = catalog
+            .query(&PresenterQuery::new().gender("female").driver_id("d2"))
+            .await
+            .unwrap();
+        assert_eq!(ids(with_driver), vec!["cat"]);
+    }
+
+    #[test]
+    fn ownership_is_unknown_without_an_account_owner() {
+        let catalog = PresenterCatalog::default();
+
+        assert_eq!(catalog.ownership(&presenter("cat", "female", "me")), Ownership::Unknown);
+    }
+}
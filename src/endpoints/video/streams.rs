@@ -7,13 +7,14 @@ use webrtc::{api::APIBuilder, peer_connection::configuration::RTCConfiguration,
 
 const STREAMS_PATH: &str = "/talks/streams";
 const SDP_PATH: &str = "/sdp";
+#[allow(dead_code)]
 const ICE_PATH: &str = "/ice";
 
 #[derive(Serialize, Debug)]
 pub struct NewStreamRequestBody {
     pub source_url: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
-    pub driver_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver_url: Option<DriverUrl>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub face: Option<Face>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -33,7 +34,7 @@ impl NewStreamRequestBody {
 
         let resp = c.send_request(Full::<Bytes>::new(body.into())).await?;
 
-        let stream_resp = serde_json::from_slice::<NewStreamResponse>(&resp.as_ref())?;
+        let stream_resp = serde_json::from_slice::<NewStreamResponse>(resp.as_ref())?;
 
         Ok(stream_resp)
     }
@@ -43,7 +44,7 @@ impl NewStreamRequestBody {
 #[derive(Serialize, Debug)]
 pub struct NewStreamRequestBodyBuilder {
     pub source_url: Option<String>,
-    pub driver_url: Option<String>,
+    pub driver_url: Option<DriverUrl>,
     pub face: Option<Face>,
     pub config: Option<StreamConfig>,
 }
@@ -55,6 +56,12 @@ pub struct StreamConfig {
     pub stitch: bool,
 }
 
+impl Default for NewStreamRequestBodyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NewStreamRequestBodyBuilder {
     pub fn new() -> Self {
         Self {
@@ -70,8 +77,8 @@ impl NewStreamRequestBodyBuilder {
         self
     }
 
-    pub fn driver_url(mut self, driver_url: &str) -> Self {
-        self.driver_url = Some(driver_url.to_string());
+    pub fn driver_url(mut self, driver_url: impl Into<DriverUrl>) -> Self {
+        self.driver_url = Some(driver_url.into());
         self
     }
 
@@ -89,8 +96,8 @@ impl NewStreamRequestBodyBuilder {
         let source_url = self.source_url.ok_or(RequestBodyBuildError::SourceUrlNotSet)?;
 
         Ok(NewStreamRequestBody {
-            source_url: source_url,
-            driver_url: self.driver_url.unwrap_or_default(),
+            source_url,
+            driver_url: self.driver_url,
             face: self.face,
             config: self.config,
         })
@@ -100,17 +107,17 @@ impl NewStreamRequestBodyBuilder {
 
 #[derive(Deserialize, Debug)]
 pub struct NewStreamResponse {
-    id: String,
-    offer: Offer,
-    ice_servers: Vec<IceServer>,
-    session_id: String,
+    pub id: String,
+    pub offer: Offer,
+    pub ice_servers: Vec<IceServer>,
+    pub session_id: String,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct IceServer {
-    urls: Urls,
-    username: Option<String>,
-    credential: Option<String>,
+    pub urls: Urls,
+    pub username: Option<String>,
+    pub credential: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -122,8 +129,8 @@ pub enum Urls {
 
 #[derive(Deserialize, Debug)]
 pub struct Offer {
-    r#type: String,
-    sdp: String,
+    pub r#type: String,
+    pub sdp: String,
 }
 
 pub async fn start_stream(stream_response: NewStreamResponse) -> Result<serde_json::Value> {
@@ -154,18 +161,18 @@ pub async fn start_stream(stream_response: NewStreamResponse) -> Result<serde_js
 
     let rtc_session_offer = RTCSessionDescription::offer(stream_response.offer.sdp)?;
 
-    let _ = peer_connection.set_remote_description(rtc_session_offer).await?;
+    peer_connection.set_remote_description(rtc_session_offer).await?;
 
     let rtc_session_answer = peer_connection.create_answer(None).await?;
 
 
-    let _ = peer_connection.set_local_description(rtc_session_answer.clone()).await?;
+    peer_connection.set_local_description(rtc_session_answer.clone()).await?;
 
     //dbg!(peer_connection.connection_state());
 
     let c = ClientBuilder::new()?
         .method(POST)?
-        .path(&format!("{}/{}{}", STREAMS_PATH, stream_response.id, SDP_PATH))?
+        .path(format!("{}/{}{}", STREAMS_PATH, stream_response.id, SDP_PATH))?
         .header(ACCEPT, APPLICATION_JSON)?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
@@ -176,7 +183,7 @@ pub async fn start_stream(stream_response: NewStreamResponse) -> Result<serde_js
 
     let resp = c.send_request(Full::<Bytes>::new(body.into())).await?;
 
-    let json = serde_json::from_slice::<serde_json::Value>(&resp.as_ref())?;
+    let json = serde_json::from_slice::<serde_json::Value>(resp.as_ref())?;
 
     Ok(json)
 
@@ -199,9 +206,9 @@ impl StartStreamRequestBody {
         Self {
             answer: Answer {
                 r#type: "answer".to_string(),
-                sdp: sdp,
+                sdp,
             },
-            session_id: session_id,
+            session_id,
         }
     }
 }
@@ -0,0 +1,274 @@
+// File: talk_groups.rs
+// Path: src/endpoints/video/talk_groups.rs
+
+use super::*;
+use super::talks::{get_talk, CreateTalkResponse};
+use std::time::{Duration, Instant};
+
+/// Splits a text script into segments of at most `max_len` characters.
+/// Segments end on sentence boundaries where possible, falling back to word
+/// boundaries for overlong sentences. SSML input is split outside of elements
+/// only, and every segment is wrapped in the original `<speak>` tag.
+pub fn split_script_input(input: &str, ssml: bool, max_len: usize) -> Result<Vec<String>> {
+    if char_len(input) <= max_len {
+        return Ok(vec![input.to_string()]);
+    }
+
+    let (open, body, close) = if ssml {
+        speak_wrapper(input)
+    } else {
+        ("", input.trim(), "")
+    };
+    let budget = max_len.saturating_sub(char_len(open) + char_len(close));
+
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    for sentence in split_units(body, false, ssml) {
+        for piece in fit(sentence, budget, ssml)? {
+            if !current.is_empty() && char_len(&current) + char_len(piece) > budget {
+                segments.push(current.trim().to_string());
+                current.clear();
+            }
+            current.push_str(piece);
+        }
+    }
+    if !current.trim().is_empty() {
+        segments.push(current.trim().to_string());
+    }
+
+    Ok(segments
+        .into_iter()
+        .map(|segment| format!("{}{}{}", open, segment, close))
+        .collect())
+}
+
+fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+/// Separates `<speak ...>` and `</speak>` from the SSML they wrap.
+fn speak_wrapper(input: &str) -> (&str, &str, &str) {
+    let trimmed = input.trim();
+    if trimmed.starts_with("<speak") && trimmed.ends_with("</speak>") {
+        if let Some(end) = trimmed.find('>') {
+            let close = trimmed.len() - "</speak>".len();
+            if end < close {
+                return (&trimmed[..=end], &trimmed[end + 1..close], &trimmed[close..]);
+            }
+        }
+    }
+    ("", trimmed, "")
+}
+
+/// Breaks `sentence` into pieces no longer than `budget`.
+fn fit(sentence: &str, budget: usize, ssml: bool) -> Result<Vec<&str>> {
+    if char_len(sentence) <= budget {
+        return Ok(vec![sentence]);
+    }
+
+    let mut pieces = Vec::new();
+    for word in split_units(sentence, true, ssml) {
+        if char_len(word) <= budget {
+            pieces.push(word);
+        } else if ssml {
+            // Cutting through an element would produce invalid SSML
+            return Err(Box::new(TalkGroupError::UnsplittableSegment(char_len(word))));
+        } else {
+            let mut rest = word;
+            while !rest.is_empty() {
+                let cut = rest
+                    .char_indices()
+                    .nth(budget.max(1))
+                    .map_or(rest.len(), |(i, _)| i);
+                pieces.push(&rest[..cut]);
+                rest = &rest[cut..];
+            }
+        }
+    }
+    Ok(pieces)
+}
+
+/// Splits `text` after sentence endings, line breaks and closing `<s>`, `<p>`
+/// or `<break/>` tags, or after every run of whitespace when `words` is set.
+/// With `ssml` set, tags are tracked and only positions outside of elements
+/// are considered; plain text treats `<` and `>` as ordinary characters.
+fn split_units(text: &str, words: bool, ssml: bool) -> Vec<&str> {
+    let mut units = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let mut boundary = false;
+
+        if ssml && c == '<' {
+            let Some(len) = text[i..].find('>') else {
+                break;
+            };
+            let end = i + len;
+            let tag = &text[i..=end];
+            while chars.next_if(|&(j, _)| j <= end).is_some() {}
+
+            if tag.starts_with("</") {
+                depth = depth.saturating_sub(1);
+                boundary = !words && depth == 0 && (tag == "</s>" || tag == "</p>");
+            } else if tag.ends_with("/>") {
+                boundary = !words && depth == 0 && tag.starts_with("<break");
+            } else if !tag.starts_with("<?") && !tag.starts_with("<!") {
+                depth += 1;
+            }
+        } else if depth == 0 {
+            boundary = if words {
+                c.is_whitespace()
+            } else {
+                c == '\n'
+                    || (matches!(c, '.' | '!' | '?')
+                        && chars.peek().is_none_or(|&(_, n)| n.is_whitespace()))
+            };
+        }
+
+        if boundary {
+            while chars.next_if(|&(_, n)| n.is_whitespace()).is_some() {}
+            let end = chars.peek().map_or(text.len(), |&(j, _)| j);
+            units.push(&text[start..end]);
+            start = end;
+        }
+    }
+
+    if start < text.len() {
+        units.push(&text[start..]);
+    }
+    units
+}
+
+/// Talks created from the segments of one long script, in script order.
+#[derive(Debug)]
+pub struct TalkGroup {
+    pub talks: Vec<CreateTalkResponse>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TalkSegment {
+    pub id: String,
+    pub result_url: String,
+    pub duration: Option<f64>,
+}
+
+/// Results of a finished `TalkGroup`, in script order.
+#[derive(Debug, Clone)]
+pub struct TalkManifest {
+    pub segments: Vec<TalkSegment>,
+}
+
+impl TalkManifest {
+    pub fn total_duration(&self) -> f64 {
+        self.segments.iter().filter_map(|s| s.duration).sum()
+    }
+}
+
+impl TalkGroup {
+    /// Polls every talk of the group until all are done, failing as soon as one
+    /// of them errors or `timeout` elapses.
+    pub async fn wait(&self, poll_interval: Duration, timeout: Duration) -> Result<TalkManifest> {
+        let deadline = Instant::now() + timeout;
+        let mut segments: Vec<Option<TalkSegment>> = vec![None; self.talks.len()];
+
+        loop {
+            for (index, talk) in self.talks.iter().enumerate() {
+                if segments[index].is_some() {
+                    continue;
+                }
+
+                let resp = get_talk(&talk.id).await?;
+                match resp.status.as_str() {
+                    "done" => {
+                        segments[index] = Some(TalkSegment {
+                            id: resp.id,
+                            result_url: resp.result_url,
+                            duration: resp.duration,
+                        })
+                    }
+                    "error" | "rejected" => {
+                        return Err(Box::new(TalkGroupError::SegmentFailed {
+                            index,
+                            id: resp.id,
+                            status: resp.status,
+                        }))
+                    }
+                    _ => {}
+                }
+            }
+
+            if segments.iter().all(Option::is_some) {
+                return Ok(TalkManifest {
+                    segments: segments.into_iter().flatten().collect(),
+                });
+            }
+
+            if Instant::now() + poll_interval > deadline {
+                return Err(Box::new(TalkGroupError::TimedOut));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_input_is_not_split() {
+        let got = split_script_input("Hello world!", false, 100).unwrap();
+
+        assert_eq!(got, vec!["Hello world!".to_string()]);
+    }
+
+    #[test]
+    fn text_input_is_split_on_sentences() {
+        let input = "First sentence here. Second one! Third one? Fourth.";
+
+        let got = split_script_input(input, false, 25).unwrap();
+
+        assert_eq!(
+            got,
+            vec!["First sentence here.", "Second one! Third one?", "Fourth."]
+        );
+        assert!(got.iter().all(|s| s.chars().count() <= 25));
+    }
+
+    #[test]
+    fn overlong_sentence_is_split_on_words() {
+        let input = "one two three four five six seven eight nine ten";
+
+        let got = split_script_input(input, false, 15).unwrap();
+
+        assert!(got.iter().all(|s| s.chars().count() <= 15));
+        assert_eq!(got.join(" "), input);
+    }
+
+    #[test]
+    fn angle_brackets_in_text_input_are_not_tags() {
+        let input = "Is x < 5? Then y > 3. Otherwise z <= 1 holds.";
+
+        let got = split_script_input(input, false, 25).unwrap();
+
+        assert_eq!(got, vec!["Is x < 5? Then y > 3.", "Otherwise z <= 1 holds."]);
+    }
+
+    #[test]
+    fn ssml_input_is_split_outside_elements() {
+        let input = "<speak><s>Hello there.</s><prosody rate=\"slow\">Slow. Words.</prosody><break time=\"1s\"/>Bye now.</speak>";
+
+        let got = split_script_input(input, true, 80).unwrap();
+
+        assert_eq!(
+            got,
+            vec![
+                "<speak><s>Hello there.</s></speak>",
+                "<speak><prosody rate=\"slow\">Slow. Words.</prosody><break time=\"1s\"/></speak>",
+                "<speak>Bye now.</speak>",
+            ]
+        );
+    }
+}
@@ -5,6 +5,7 @@
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::endpoints::resources::settings::Settings;
 
     #[test]
     fn talk_request_body_is_formatting_with_text_script() {
@@ -14,10 +15,7 @@ mod tests {
             script: Script::Text {
                 r#type: "text".to_string(),
                 subtitles: false,
-                provider: Some(TTSProvider::MicrosoftTTS {
-                    r#type: "microsoft".to_string(),
-                    voice_id: "en-US-JennyNeural".to_string(),
-                }),
+                provider: None,
                 input: "Hello world!".to_string(),
                 ssml: false,
             },
@@ -27,10 +25,10 @@ mod tests {
             webhook: "".to_string(),
             result_url: "".to_string(),
             face: None,
-            persist: false,
+            persist: None,
         };
 
-        let talk_req_bod2 = TalkRequestBodyBuilder::with_text_script().unwrap()
+        let talk_req_bod2 = TalkRequestBodyBuilder::with_text_script()
             .source_url("www.dummyurl.com").unwrap()
             .input("Hello world!").unwrap()
             .build().unwrap();
@@ -60,10 +58,10 @@ mod tests {
             webhook: "".to_string(),
             result_url: "".to_string(),
             face: None,
-            persist: false,
+            persist: None,
         };
 
-        let talk_req_bod2 = TalkRequestBodyBuilder::with_audio_script().unwrap()
+        let talk_req_bod2 = TalkRequestBodyBuilder::with_audio_script()
             .source_url("www.dummyurl.com").unwrap()
             .audio_url("www.dummyaudiourl.com").unwrap()
             .build().unwrap();
@@ -73,19 +71,140 @@ mod tests {
 
         assert_eq!(want, got);
     }
+
+    #[test]
+    fn account_settings_fill_unset_values() {
+        let settings = serde_json::from_value::<Settings>(serde_json::json!({
+            "webhook": "https://example.com/hook",
+            "tts": {"type": "microsoft", "voice_id": "en-GB-SoniaNeural"},
+            "result_format": "mov",
+        }))
+        .unwrap();
+
+        let defaults = RequestDefaults::from_settings(&settings);
+
+        let defaulted = TalkRequestBodyBuilder::with_text_script()
+            .source_url("www.dummyurl.com").unwrap()
+            .config(ConfigBuilder::new().stitch(true).build().unwrap()).unwrap()
+            .build().unwrap()
+            .with_defaults(&defaults);
+        let explicit = TalkRequestBodyBuilder::with_text_script()
+            .source_url("www.dummyurl.com").unwrap()
+            .provider(TTSProvider::microsoft("en-US-GuyNeural", None)).unwrap()
+            .webhook("https://example.com/other").unwrap()
+            .config(ConfigBuilder::new().result_format(ResultFormat::Gif).build().unwrap()).unwrap()
+            .build().unwrap()
+            .with_defaults(&defaults);
+
+        let defaulted = serde_json::to_value(defaulted).unwrap();
+        assert_eq!(defaulted["webhook"], "https://example.com/hook");
+        assert_eq!(defaulted["script"]["provider"]["voice_id"], "en-GB-SoniaNeural");
+        assert_eq!(defaulted["config"], serde_json::json!({"stitch": true, "result_format": "mov"}));
+
+        let explicit = serde_json::to_value(explicit).unwrap();
+        assert_eq!(explicit["webhook"], "https://example.com/other");
+        assert_eq!(explicit["script"]["provider"]["voice_id"], "en-US-GuyNeural");
+        assert_eq!(explicit["config"], serde_json::json!({"result_format": "gif"}));
+    }
+
+    #[test]
+    fn request_defaults_do_not_override_explicit_values() {
+        let defaults = RequestDefaults::new()
+            .webhook("https://example.com/hook")
+            .persist(true)
+            .result_url_prefix("s3://bucket/videos/")
+            .provider(TTSProvider::microsoft("en-GB-SoniaNeural", None));
+
+        let defaulted = TalkRequestBodyBuilder::with_text_script()
+            .source_url("www.dummyurl.com").unwrap()
+            .build().unwrap()
+            .with_defaults(&defaults);
+        let explicit = TalkRequestBodyBuilder::with_text_script()
+            .source_url("www.dummyurl.com").unwrap()
+            .provider(TTSProvider::microsoft("en-US-GuyNeural", None)).unwrap()
+            .persist(false).unwrap()
+            .result_url("s3://bucket/launch.mp4").unwrap()
+            .build().unwrap()
+            .with_defaults(&defaults);
+
+        let defaulted = serde_json::to_value(defaulted).unwrap();
+        assert_eq!(defaulted["webhook"], "https://example.com/hook");
+        assert_eq!(defaulted["persist"], true);
+        assert_eq!(defaulted["script"]["provider"]["voice_id"], "en-GB-SoniaNeural");
+
+        let explicit = serde_json::to_value(explicit).unwrap();
+        assert_eq!(explicit["webhook"], "https://example.com/hook");
+        assert_eq!(explicit["persist"], false);
+        assert_eq!(explicit["script"]["provider"]["voice_id"], "en-US-GuyNeural");
+        assert_eq!(explicit["result_url"], "s3://bucket/launch.mp4");
+        assert_ne!(defaulted["result_url"], explicit["result_url"]);
+    }
+
+    #[test]
+    fn split_talks_write_to_their_own_result_url() {
+        let talk = TalkRequestBodyBuilder::with_text_script()
+            .source_url("www.dummyurl.com").unwrap()
+            .input("First sentence here. Second sentence here. Third sentence here.").unwrap()
+            .result_url("s3://bucket/videos/launch.mp4?sig=abc").unwrap()
+            .build().unwrap();
+
+        let urls = talk.split(25).unwrap().into_iter().map(|t| t.result_url).collect::<Vec<_>>();
+
+        assert_eq!(
+            urls,
+            vec![
+                "s3://bucket/videos/launch-1of3.mp4?sig=abc",
+                "s3://bucket/videos/launch-2of3.mp4?sig=abc",
+                "s3://bucket/videos/launch-3of3.mp4?sig=abc",
+            ]
+        );
+    }
+
+    #[test]
+    fn talk_is_localized() {
+        let base = TalkRequestBodyBuilder::with_text_script()
+            .source_url("www.dummyurl.com").unwrap()
+            .input("Hello world!").unwrap()
+            .name("launch").unwrap()
+            .user_data("campaign-7").unwrap()
+            .result_url("s3://bucket/videos/launch.mp4").unwrap()
+            .build().unwrap();
+        let translation = Translation::new("Olá mundo!")
+            .provider(TTSProvider::microsoft("pt-BR-FranciscaNeural", None));
+
+        let got = serde_json::to_value(base.localize("pt-BR", &translation).unwrap()).unwrap();
+
+        assert_eq!(got["name"], "launch [pt-BR]");
+        assert_eq!(got["user_data"], "campaign-7");
+        assert_eq!(got["result_url"], "s3://bucket/videos/launch-pt-BR.mp4");
+        assert_eq!(got["script"]["input"], "Olá mundo!");
+        assert_eq!(got["script"]["provider"]["voice_id"], "pt-BR-FranciscaNeural");
+
+        let audio = TalkRequestBodyBuilder::with_audio_script()
+            .source_url("www.dummyurl.com").unwrap()
+            .audio_url("www.dummyaudiourl.com").unwrap()
+            .build().unwrap();
+        assert!(audio.localize("pt-BR", &translation).is_err());
+    }
 }
 
 
 use super::*;
+use super::localized_talks::{LocalizedTalks, Translation};
+use super::talk_groups::{split_script_input, TalkGroup};
+use futures_util::stream::{self, StreamExt};
+use std::collections::BTreeMap;
+use super::defaults::RequestDefaults;
+use crate::endpoints::resources::credits::CreditGuard;
 
 
 const TALKS_PATH: &str = "/talks";
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TalkRequestBody {
     source_url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    driver_url: Option<Driver>,
+    driver_url: Option<DriverUrl>,
     script: Script,
     #[serde(skip_serializing_if = "Option::is_none")]
     config: Option<Config>,
@@ -99,10 +218,35 @@ pub struct TalkRequestBody {
     result_url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     face: Option<Face>,
-    persist: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    persist: Option<bool>,
 }
 
 impl TalkRequestBody {
+    pub fn script(&self) -> &Script {
+        &self.script
+    }
+
+    pub fn config(&self) -> Option<&Config> {
+        self.config.as_ref()
+    }
+
+    /// This talk with the values it leaves unset taken from `defaults`,
+    /// including a result url of its own under their prefix.
+    pub fn with_defaults(mut self, defaults: &RequestDefaults) -> Self {
+        defaults.fill_webhook(&mut self.webhook);
+        defaults.fill_persist(&mut self.persist);
+        defaults.fill_config(&mut self.config);
+        defaults.fill_result_url(&mut self.result_url, self.config.as_ref());
+        defaults.fill_provider(&mut self.script);
+        self
+    }
+
+    /// Sends this talk with the values it leaves unset taken from `defaults`.
+    pub async fn create_talk_with(self, defaults: &RequestDefaults) -> Result<CreateTalkResponse> {
+        self.with_defaults(defaults).create_talk().await
+    }
+
     pub async fn create_talk(&self) -> Result<CreateTalkResponse> {
         let c = ClientBuilder::new()?
             .method(POST)?
@@ -112,23 +256,133 @@ impl TalkRequestBody {
 
         let resp = c.send_request(Full::<Bytes>::new(serde_json::to_string(&self)?.into())).await?;
 
-        let talk_resp= serde_json::from_slice::<CreateTalkResponse>(&resp.as_ref())?;
+        let talk_resp= serde_json::from_slice::<CreateTalkResponse>(resp.as_ref())?;
 
         Ok(talk_resp)
     }
 
+    /// Splits a text script longer than `max_len` characters into several talks
+    /// sharing the source, voice and config of this one.
+    /// Each talk writes to the result url with a `-{n}of{count}` suffix.
+    /// Audio scripts and short text scripts are returned as a single talk.
+    pub fn split(&self, max_len: usize) -> Result<Vec<TalkRequestBody>> {
+        let Script::Text { input, ssml, .. } = &self.script else {
+            return Ok(vec![self.clone()]);
+        };
+
+        let segments = split_script_input(input, *ssml, max_len)?;
+        let count = segments.len();
+
+        Ok(segments
+            .into_iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let mut body = self.clone();
+                if let Script::Text { input, .. } = &mut body.script {
+                    *input = segment;
+                }
+                if count > 1 && !body.name.is_empty() {
+                    body.name = format!("{} ({}/{})", body.name, i + 1, count);
+                }
+                if count > 1 {
+                    body.result_url = suffix_result_url(&body.result_url, &format!("-{}of{}", i + 1, count));
+                }
+                body
+            })
+            .collect())
+    }
 
+    async fn create_talk_guarded(&self, guard: Option<&CreditGuard>) -> Result<CreateTalkResponse> {
+        match guard {
+            Some(guard) => guard.create_talk(self).await,
+            None => self.create_talk().await,
+        }
+    }
+
+    /// Creates one talk per segment of the script, see `split`, charging each
+    /// against `guard` if one is given.
+    /// The talks are submitted in script order. If one of them cannot be
+    /// created, `TalkGroupError::CreateFailed` carries the ids of the talks
+    /// created before it so they can be polled or deleted.
+    pub async fn create_talk_group(
+        &self,
+        max_len: usize,
+        guard: Option<&CreditGuard>,
+    ) -> Result<TalkGroup> {
+        let mut talks: Vec<CreateTalkResponse> = Vec::new();
+        for (index, body) in self.split(max_len)?.into_iter().enumerate() {
+            match body.create_talk_guarded(guard).await {
+                Ok(talk) => talks.push(talk),
+                Err(source) => {
+                    return Err(Box::new(TalkGroupError::CreateFailed {
+                        index,
+                        created: talks.into_iter().map(|t| t.id).collect(),
+                        source,
+                    }))
+                }
+            }
+        }
+
+        Ok(TalkGroup { talks })
+    }
+
+    /// This talk speaking `translation` in `locale`.
+    /// The locale is added to the name so listings can tell the variants
+    /// apart, and to the result url as a `-{locale}` suffix so each variant is
+    /// written to its own file. The user data is left as set by the caller.
+    pub fn localize(&self, locale: &str, translation: &Translation) -> Result<TalkRequestBody> {
+        let mut body = self.clone();
+        let Script::Text { input, provider, .. } = &mut body.script else {
+            return Err(Box::new(LocalizationError::AudioScript));
+        };
+        *input = translation.input.clone();
+        if let Some(voice) = &translation.provider {
+            *provider = Some(voice.clone());
+        }
+
+        body.name = if body.name.is_empty() {
+            locale.to_string()
+        } else {
+            format!("{} [{}]", body.name, locale)
+        };
+        body.result_url = suffix_result_url(&body.result_url, &format!("-{}", locale));
+        Ok(body)
+    }
+
+    /// Creates one talk per locale of `translations`, at most `concurrency`
+    /// at a time, charging each against `guard` if one is given.
+    /// A failing locale does not stop the others.
+    pub async fn create_localized_talks(
+        &self,
+        translations: &BTreeMap<String, Translation>,
+        concurrency: usize,
+        guard: Option<&CreditGuard>,
+    ) -> LocalizedTalks {
+        let results = stream::iter(translations)
+            .map(|(locale, translation)| async move {
+                let talk = match self.localize(locale, translation) {
+                    Ok(body) => body.create_talk_guarded(guard).await,
+                    Err(e) => Err(e),
+                };
+                (locale.clone(), talk)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect::<BTreeMap<_, _>>()
+            .await;
+
+        LocalizedTalks { results }
+    }
 }
 pub async fn get_talk(id: &str) -> Result<GetTalkResponse> {
     let c = ClientBuilder::new()?
         .method(GET)?
-        .path(&format!("{}/{}", TALKS_PATH, id))?
+        .path(format!("{}/{}", TALKS_PATH, id))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let json = serde_json::from_slice::<GetTalkResponse>(&resp.as_ref())?;
+    let json = serde_json::from_slice::<GetTalkResponse>(resp.as_ref())?;
 
     Ok(json)
 }
@@ -142,7 +396,7 @@ pub async fn get_talks() -> Result<GetTalksResponse> {
 
     let resp = c.send_request(Empty::<Bytes>::new()).await?;
 
-    let json = serde_json::from_slice::<GetTalksResponse>(&resp.as_ref())?;
+    let json = serde_json::from_slice::<GetTalksResponse>(resp.as_ref())?;
 
     Ok(json)
 }
@@ -150,7 +404,7 @@ pub async fn get_talks() -> Result<GetTalksResponse> {
 pub async fn delete_talk(id: &str) -> Result<()> {
     let c = ClientBuilder::new()?
         .method(DELETE)?
-        .path(&format!("{}/{}", TALKS_PATH, id))?
+        .path(format!("{}/{}", TALKS_PATH, id))?
         .header(CONTENT_TYPE, APPLICATION_JSON)?
         .build()?;
 
@@ -174,11 +428,16 @@ pub struct GetTalkResponse {
     pub user_id: String,
     pub source_url: String,
     pub created_at: String,
+    #[serde(default)]
     pub audio_url: String,
+    #[serde(default)]
     pub started_at: String,
     pub modified_at: String,
     pub status: String,
+    #[serde(default)]
     pub result_url: String,
+    #[serde(default)]
+    pub duration: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -190,7 +449,7 @@ pub struct GetTalksResponse {
 
 pub struct TalkRequestBodyBuilder {
     source_url: Option<String>,
-    driver_url: Option<Driver>,
+    driver_url: Option<DriverUrl>,
     script: Option<Script>,
     config: Option<Config>,
     user_data: Option<String>,
@@ -206,10 +465,7 @@ impl TalkRequestBodyBuilder {
         let script = Script::Text {
             r#type: "text".to_string(),
             subtitles: false,
-            provider: Some(TTSProvider::MicrosoftTTS {
-                r#type: "microsoft".to_string(),
-                voice_id: "en-US-JennyNeural".to_string(),
-            }),
+            provider: None,
             input: "".to_string(),
             ssml: false,
         };
@@ -261,8 +517,8 @@ impl TalkRequestBodyBuilder {
         Ok(self)
     }
 
-    pub fn driver_url(mut self, driver_url: Driver) -> Result<Self> {
-        self.driver_url = Some(driver_url);
+    pub fn driver_url(mut self, driver_url: impl Into<DriverUrl>) -> Result<Self> {
+        self.driver_url = Some(driver_url.into());
         Ok(self)
     }
 
@@ -317,6 +573,8 @@ impl TalkRequestBodyBuilder {
         Ok(self)
     }
 
+    /// Voice of the text script. Without one the talk speaks with the voice
+    /// of the defaults it is sent with, or else with D-ID's default voice.
     pub fn provider(mut self, provider: TTSProvider) -> Result<Self> {
         if let Some(Script::Text { provider: p, .. }) = self.script.as_mut() {
             *p = Some(provider);
@@ -359,7 +617,7 @@ impl TalkRequestBodyBuilder {
                 webhook: self.webhook.unwrap_or_default(),
                 result_url: self.result_url.unwrap_or_default(),
                 face: self.face,
-                persist: self.persist.unwrap_or(false),
+                persist: self.persist,
             }
         )
 
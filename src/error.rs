@@ -14,4 +14,123 @@ pub enum RequestBodyBuildError {
     ScriptNotSet,
     #[error("presenter id must be set")]
     PresenterIdNotSet,
+    #[error("{field} must be between {min} and {max}, got {value}")]
+    OutOfRange {
+        field: &'static str,
+        min: f32,
+        max: f32,
+        value: f32,
+    },
+    #[error("invalid timestamp: {0}")]
+    InvalidTimestamp(f32),
+    #[error("expressions must be in order, frame {next} follows frame {previous}")]
+    ExpressionsOutOfOrder { previous: u32, next: u32 },
+    #[error("driver url must start with s3:// or https://, got {0}")]
+    InvalidDriverUrl(String),
+    #[error("unknown bank driver: {0}")]
+    UnknownDriver(String),
+    #[error("driver {driver_id} is not available for presenter {presenter_id}, valid drivers: {}", valid.join(", "))]
+    IncompatibleDriver {
+        presenter_id: String,
+        driver_id: String,
+        valid: Vec<String>,
+    },
+    #[error("invalid hex color: {0}")]
+    InvalidColor(String),
+    #[error("background color or source url must be set")]
+    BackgroundNotSet,
+    #[error("crop rectangle must have left < right and top < bottom")]
+    InvalidRectangle,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DownloadError {
+    #[error("unexpected status code: {0}")]
+    UnexpectedStatus(u16),
+    #[error("invalid content-range header: {0}")]
+    InvalidContentRange(String),
+    #[error("incomplete download: expected {expected} bytes, received {received}")]
+    Incomplete { expected: u64, received: u64 },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TalkGroupError {
+    #[error("script segment of {0} characters cannot be split under the input limit")]
+    UnsplittableSegment(usize),
+    #[error("talk {id} for segment {index} finished with status {status}")]
+    SegmentFailed {
+        index: usize,
+        id: String,
+        status: String,
+    },
+    #[error("timed out waiting for talks to finish")]
+    TimedOut,
+    /// Creating the talk of segment `index` failed. The talks of the earlier
+    /// segments were already created, their ids are in `created`.
+    #[error("creating the talk for segment {index} failed: {source}")]
+    CreateFailed {
+        index: usize,
+        created: Vec<String>,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum LocalizationError {
+    #[error("only talks with a text script can be localized")]
+    AudioScript,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EstimateError {
+    #[error("duration of audio {0} is unknown")]
+    UnknownAudioDuration(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("insufficient credits: {required} required, {remaining} remaining with a reserve of {reserve}")]
+pub struct InsufficientCredits {
+    pub remaining: i64,
+    pub required: u32,
+    pub reserve: i64,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum UploadError {
+    #[error("unrecognized file format, expected JPEG, PNG, WAV, MP3, MP4, M4A, OGG or WebM")]
+    UnknownMediaType,
+    #[error("{mime_type} cannot be uploaded as {expected}")]
+    UnsupportedMediaType {
+        expected: &'static str,
+        mime_type: &'static str,
+    },
+    #[error("file of {size} bytes exceeds the upload limit of {max} bytes")]
+    TooLarge { size: u64, max: u64 },
+    #[error("image of {width}x{height} exceeds the upload limit of {max_width}x{max_height}")]
+    ImageTooLarge {
+        width: u32,
+        height: u32,
+        max_width: u32,
+        max_height: u32,
+    },
+    #[error("audio of {duration:.1}s exceeds the upload limit of {max}s")]
+    AudioTooLong { duration: f64, max: f64 },
+    #[error("remote files cannot be processed before upload")]
+    RemoteSource,
+    #[error("audio is silent throughout, nothing is left after trimming")]
+    SilentAudio,
+    #[error("audio cannot be normalized to a sample rate of 0")]
+    ZeroSampleRate,
+    #[error("crop box lies outside of the {width}x{height} image")]
+    CropOutOfBounds { width: u32, height: u32 },
+    #[error("no tracked upload has the id or url {0}")]
+    UntrackedUpload(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum VoiceError {
+    #[error("invalid language tag: {0}")]
+    InvalidLanguageTag(String),
+    #[error("no voice speaks {0} and no default voice is set")]
+    NoMatchingVoice(String),
 }
@@ -5,5 +5,6 @@ pub const POST: &str = "POST";
 pub const ACCEPT: &str = "accept";
 pub const APPLICATION_JSON: &str = "application/json";
 pub const CONTENT_TYPE: &str = "Content-Type";
+pub const CONTENT_LENGTH: &str = "Content-Length";
 pub const IMAGE_PNG: &str = "image/png";
 pub const MULTIPART_FORM_DATA_BOUNDARY: &str = "multipart/form-data; boundary=";
@@ -0,0 +1,93 @@
+//! Sample processing for audio normalization
+
+use std::f64::consts::PI;
+
+/// Zero crossings of the resampling filter on each side of a sample.
+const SINC_ZERO_CROSSINGS: f64 = 16.0;
+
+/// Averages all channels into one.
+pub fn downmix(channels: &[Vec<f32>]) -> Vec<f32> {
+    let len = channels.iter().map(Vec::len).min().unwrap_or_default();
+    (0..len)
+        .map(|i| channels.iter().map(|c| c[i]).sum::<f32>() / channels.len() as f32)
+        .collect()
+}
+
+/// Band-limited resampling with a Hann windowed sinc filter, cutting off at
+/// the lower of the two Nyquist frequencies so downsampling does not alias.
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let step = from as f64 / to as f64;
+    let cutoff = (1.0 / step).min(1.0);
+    let half_width = SINC_ZERO_CROSSINGS / cutoff;
+    let len = (samples.len() as f64 / step).round() as usize;
+
+    (0..len)
+        .map(|n| {
+            let t = n as f64 * step;
+            let first = (t - half_width).ceil().max(0.0) as usize;
+            let last = ((t + half_width).floor() as usize).min(samples.len() - 1);
+            let mut sum = 0.0;
+            for (k, sample) in samples.iter().enumerate().take(last + 1).skip(first) {
+                let x = t - k as f64;
+                let window = 0.5 + 0.5 * (PI * x / half_width).cos();
+                sum += *sample as f64 * cutoff * sinc(cutoff * x) * window;
+            }
+            sum as f32
+        })
+        .collect()
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// The samples between the first and the last one louder than `threshold`.
+pub fn trim_silence(samples: &[f32], threshold: f32) -> &[f32] {
+    let loud = |s: &f32| s.abs() > threshold;
+    match (samples.iter().position(loud), samples.iter().rposition(loud)) {
+        (Some(first), Some(last)) => &samples[first..=last],
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stereo_is_downmixed() {
+        assert_eq!(downmix(&[vec![1.0, 0.5], vec![0.0, -0.5]]), vec![0.5, 0.0]);
+    }
+
+    #[test]
+    fn resampling_keeps_tones_and_length() {
+        let tone = |rate: u32, len: usize| {
+            (0..len)
+                .map(|i| (2.0 * PI * 440.0 * i as f64 / rate as f64).sin() as f32)
+                .collect::<Vec<_>>()
+        };
+
+        let got = resample(&tone(48000, 4800), 48000, 16000);
+        let expected = tone(16000, 1600);
+
+        assert_eq!(got.len(), 1600);
+        // Away from the edges, where the filter runs out of input
+        for i in 100..1500 {
+            assert!((got[i] - expected[i]).abs() < 0.01, "sample {}", i);
+        }
+    }
+
+    #[test]
+    fn silence_is_trimmed() {
+        assert_eq!(trim_silence(&[0.0, 0.001, 0.5, 0.0, -0.3, 0.002], 0.01), &[0.5, 0.0, -0.3]);
+        assert!(trim_silence(&[0.0, 0.001], 0.01).is_empty());
+    }
+}
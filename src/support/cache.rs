@@ -0,0 +1,41 @@
+//! Time limited caching for catalogs loaded from the api
+use crate::prelude::Result;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// How long a catalog keeps what it loaded before reloading it by default.
+pub const DEFAULT_CATALOG_TTL: Duration = Duration::from_secs(600);
+
+/// A value loaded on first use and reloaded once it is older than `ttl`.
+#[derive(Debug)]
+pub struct TtlCache<T> {
+    ttl: Duration,
+    entry: Option<(Instant, T)>,
+}
+
+impl<T> TtlCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, entry: None }
+    }
+
+    pub fn invalidate(&mut self) {
+        self.entry = None;
+    }
+
+    /// Stores `value` as if it had just been loaded.
+    pub fn insert(&mut self, value: T) -> &T {
+        &self.entry.insert((Instant::now(), value)).1
+    }
+
+    /// The cached value, loaded with `load` if it is missing or stale.
+    pub async fn get_or_load<F, Fut>(&mut self, load: F) -> Result<&T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        match self.entry.take() {
+            Some((at, value)) if at.elapsed() < self.ttl => Ok(&self.entry.insert((at, value)).1),
+            _ => Ok(self.insert(load().await?)),
+        }
+    }
+}
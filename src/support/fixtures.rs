@@ -0,0 +1,64 @@
+//! Models shared by the unit tests
+use crate::endpoints::resources::credits::{CreditResponse, Info};
+use crate::endpoints::resources::voices::Voice;
+use crate::endpoints::video::clips::{ClipDriver, Presenter};
+
+pub fn presenter(presenter_id: &str, gender: &str, owner_id: &str) -> Presenter {
+    Presenter {
+        presenter_id: presenter_id.to_string(),
+        driver_id: format!("{}-driver", presenter_id),
+        gender: gender.to_string(),
+        owner_id: owner_id.to_string(),
+        preview_url: "".to_string(),
+        modified_at: "".to_string(),
+    }
+}
+
+pub fn driver(presenter_id: &str, driver_id: &str) -> ClipDriver {
+    ClipDriver {
+        created_at: "".to_string(),
+        driver_id: driver_id.to_string(),
+        driver_image_url: "".to_string(),
+        gender: "".to_string(),
+        modified_at: "".to_string(),
+        name: "".to_string(),
+        presenter_id: presenter_id.to_string(),
+        preview_url: "".to_string(),
+        thumbnail_url: "".to_string(),
+        video_url: "".to_string(),
+    }
+}
+
+pub fn voice(id: &str, locale: &str, gender: &str, provider: &str, styles: &[&str]) -> Voice {
+    serde_json::from_value(serde_json::json!({
+        "id": id, "name": id, "gender": gender, "locale": locale, "language": "",
+        "access": "public", "provider": provider, "styles": styles,
+    }))
+    .unwrap()
+}
+
+/// Credits made of `(product_id, created_at, expire_at, remaining, total)` packs.
+pub fn credits(packs: &[(&str, &str, &str, i64, i64)]) -> CreditResponse {
+    let credits = packs
+        .iter()
+        .map(|(product_id, created_at, expire_at, remaining, total)| {
+            serde_json::from_value::<Info>(serde_json::json!({
+                "owner_id": "owner",
+                "expire_at": expire_at,
+                "created_at": created_at,
+                "remaining": remaining,
+                "valid_from": created_at,
+                "total": total,
+                "product_id": product_id,
+                "modified_at": created_at,
+            }))
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    CreditResponse {
+        remaining: credits.iter().map(|c| c.remaining).sum(),
+        total: credits.iter().map(|c| c.total).sum(),
+        credits,
+    }
+}
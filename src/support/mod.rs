@@ -1,2 +1,9 @@
 mod tokiort;
-pub use tokiort::{TokioExecutor, TokioIo, TokioTimer};
+pub mod audio;
+pub mod cache;
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub mod probe;
+pub mod sniff;
+pub mod wav;
+pub use tokiort::TokioIo;
@@ -0,0 +1,161 @@
+//! Image dimensions and audio durations read from file headers
+
+use super::sniff::MediaType;
+use super::wav::read_wav_info;
+use std::io::Cursor;
+
+/// Width and height of a PNG or JPEG image.
+pub fn image_dimensions(media_type: MediaType, head: &[u8]) -> Option<(u32, u32)> {
+    match media_type {
+        MediaType::Png => png_dimensions(head),
+        MediaType::Jpeg => jpeg_dimensions(head),
+        _ => None,
+    }
+}
+
+/// Duration in seconds of a WAV or MP3 file of `len` bytes starting with `head`.
+pub fn audio_duration(media_type: MediaType, head: &[u8], len: u64) -> Option<f64> {
+    match media_type {
+        MediaType::Wav => wav_duration(head, len),
+        MediaType::Mp3 => mp3_duration(head, len),
+        _ => None,
+    }
+}
+
+fn be16(b: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(b.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn be32(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn png_dimensions(head: &[u8]) -> Option<(u32, u32)> {
+    if head.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some((be32(head, 16)?, be32(head, 20)?))
+}
+
+/// Walks the JPEG segments up to the first start of frame.
+fn jpeg_dimensions(head: &[u8]) -> Option<(u32, u32)> {
+    let mut at = 2;
+    loop {
+        if *head.get(at)? != 0xFF {
+            return None;
+        }
+        // Markers may be preceded by any number of fill bytes
+        while *head.get(at + 1)? == 0xFF {
+            at += 1;
+        }
+        let marker = *head.get(at + 1)?;
+        match marker {
+            0xD8 | 0x01 | 0xD0..=0xD7 => at += 2,
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                return Some((be16(head, at + 7)?, be16(head, at + 5)?));
+            }
+            _ => at += 2 + be16(head, at + 2)? as usize,
+        }
+    }
+}
+
+fn wav_duration(head: &[u8], len: u64) -> Option<f64> {
+    let mut info = read_wav_info(&mut Cursor::new(head)).ok()?;
+    // Streamed WAV files may not know their length up front
+    info.data_len = info.data_len.min(len.saturating_sub(info.data_offset));
+    Some(info.duration())
+}
+
+const MPEG1_LAYER3_KBPS: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+const MPEG2_LAYER3_KBPS: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+const MPEG1_SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+/// Uses the frame count of a Xing, Info or VBRI header when present, and the
+/// bitrate of the first frame otherwise.
+fn mp3_duration(head: &[u8], len: u64) -> Option<f64> {
+    let mut at = 0;
+    if head.starts_with(b"ID3") {
+        let size = head
+            .get(6..10)?
+            .iter()
+            .fold(0usize, |size, b| (size << 7) | (*b & 0x7F) as usize);
+        let footer = if head.get(5)? & 0x10 != 0 { 10 } else { 0 };
+        at = 10 + size + footer;
+    }
+
+    let header = head.get(at..at + 4)?;
+    let version = (header[1] >> 3) & 0x03;
+    let layer = (header[1] >> 1) & 0x03;
+    if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 || version == 1 || layer != 1 {
+        return None;
+    }
+    let mpeg1 = version == 3;
+    let bitrate_index = (header[2] >> 4) as usize;
+    let rate_index = ((header[2] >> 2) & 0x03) as usize;
+    if bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+        return None;
+    }
+    // MPEG 2 halves the MPEG 1 sample rates, MPEG 2.5 quarters them
+    let sample_rate = MPEG1_SAMPLE_RATES[rate_index] >> (3 - version).min(2);
+    let samples_per_frame = if mpeg1 { 1152.0 } else { 576.0 };
+    let mono = header[3] >> 6 == 3;
+
+    let side_info = match (mpeg1, mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+    let xing = at + 4 + side_info;
+    let frames = match head.get(xing..xing + 4) {
+        Some(b"Xing") | Some(b"Info") if be32(head, xing + 4)? & 0x01 != 0 => be32(head, xing + 8),
+        _ if head.get(at + 36..at + 40) == Some(b"VBRI") => be32(head, at + 36 + 14),
+        _ => None,
+    };
+    if let Some(frames) = frames {
+        return Some(frames as f64 * samples_per_frame / sample_rate as f64);
+    }
+
+    let kbps = if mpeg1 {
+        MPEG1_LAYER3_KBPS[bitrate_index]
+    } else {
+        MPEG2_LAYER3_KBPS[bitrate_index]
+    };
+    Some(len.saturating_sub(at as u64) as f64 * 8.0 / (kbps as f64 * 1000.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::support::wav::pcm16_wav;
+
+    #[test]
+    fn image_dimensions_are_read() {
+        let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 0x07, 0x80, 0, 0, 0x04, 0x38]);
+        assert_eq!(image_dimensions(MediaType::Png, &png), Some((1920, 1080)));
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x06, b'J', b'F', b'I', b'F'];
+        jpeg.extend_from_slice(&[0xFF, 0xFF, 0xC2, 0x00, 0x11, 0x08, 0x01, 0xE0, 0x02, 0x80]);
+        assert_eq!(image_dimensions(MediaType::Jpeg, &jpeg), Some((640, 480)));
+        assert_eq!(image_dimensions(MediaType::Jpeg, &jpeg[..12]), None);
+    }
+
+    #[test]
+    fn audio_durations_are_read() {
+        let wav = pcm16_wav(1, 16000, &[0; 32000]);
+        assert_eq!(audio_duration(MediaType::Wav, &wav[..64], wav.len() as u64), Some(2.0));
+
+        // MPEG 1 layer III, 128kbps, 44.1kHz, after a 16 byte ID3 tag
+        let mut cbr = b"ID3\x04\x00\x00\x00\x00\x00\x06".to_vec();
+        cbr.extend_from_slice(&[0; 6]);
+        cbr.extend_from_slice(&[0xFF, 0xFB, 0x90, 0x64]);
+        assert_eq!(audio_duration(MediaType::Mp3, &cbr, 16 + 32_000), Some(2.0));
+
+        // MPEG 2 layer III, 22.05kHz, mono with a Xing header of 100 frames
+        let mut vbr = vec![0xFF, 0xF3, 0x80, 0xC4];
+        vbr.extend_from_slice(&[0; 9]);
+        vbr.extend_from_slice(b"Xing\x00\x00\x00\x01\x00\x00\x00\x64");
+        let got = audio_duration(MediaType::Mp3, &vbr, 1_000_000).unwrap();
+        assert!((got - 100.0 * 576.0 / 22050.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,109 @@
+//! Media type detection from magic bytes
+
+/// Bytes needed by `sniff` to recognize every supported format.
+pub const SNIFF_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    Jpeg,
+    Png,
+    Wav,
+    Mp3,
+    Mp4,
+    M4a,
+    Ogg,
+    WebM,
+}
+
+impl MediaType {
+    /// The MIME type D-ID expects for this format.
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            MediaType::Jpeg => "image/jpeg",
+            MediaType::Png => "image/png",
+            MediaType::Wav => "audio/wav",
+            MediaType::Mp3 => "audio/mpeg",
+            MediaType::Mp4 => "video/mp4",
+            MediaType::M4a => "audio/mp4",
+            MediaType::Ogg => "audio/ogg",
+            MediaType::WebM => "video/webm",
+        }
+    }
+
+    pub fn is_image(&self) -> bool {
+        matches!(self, MediaType::Jpeg | MediaType::Png)
+    }
+
+    /// Audio or video, both accepted by the audios endpoint.
+    pub fn is_audio(&self) -> bool {
+        !self.is_image()
+    }
+}
+
+/// Detects the format of a file from its first bytes, at most `SNIFF_LEN` of
+/// them are looked at.
+pub fn sniff(head: &[u8]) -> Option<MediaType> {
+    let head = &head[..head.len().min(SNIFF_LEN)];
+
+    if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(MediaType::Jpeg)
+    } else if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(MediaType::Png)
+    } else if head.len() >= 12 && &head[0..4] == b"RIFF" && &head[8..12] == b"WAVE" {
+        Some(MediaType::Wav)
+    } else if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        match &head[8..12] {
+            b"M4A " | b"M4B " => Some(MediaType::M4a),
+            _ => Some(MediaType::Mp4),
+        }
+    } else if head.starts_with(b"OggS") {
+        Some(MediaType::Ogg)
+    } else if head.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        // Matroska shares the EBML header, only its webm doctype is accepted
+        head.windows(4)
+            .any(|w| w == b"webm")
+            .then_some(MediaType::WebM)
+    } else if head.starts_with(b"ID3") || is_mpeg_audio_frame(head) {
+        Some(MediaType::Mp3)
+    } else {
+        None
+    }
+}
+
+/// An MPEG audio frame header: 11 sync bits, then a version and layer that
+/// are not reserved.
+fn is_mpeg_audio_frame(head: &[u8]) -> bool {
+    head.len() >= 2
+        && head[0] == 0xFF
+        && head[1] & 0xE0 == 0xE0
+        && head[1] & 0x18 != 0x08
+        && head[1] & 0x06 != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_are_sniffed() {
+        let cases: &[(&[u8], Option<MediaType>)] = &[
+            (b"\xFF\xD8\xFF\xE0\x00\x10JFIF", Some(MediaType::Jpeg)),
+            (b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR", Some(MediaType::Png)),
+            (b"RIFF\x24\x00\x00\x00WAVEfmt ", Some(MediaType::Wav)),
+            (b"ID3\x04\x00\x00", Some(MediaType::Mp3)),
+            (b"\xFF\xFB\x90\x64", Some(MediaType::Mp3)),
+            (b"\x00\x00\x00\x20ftypisom", Some(MediaType::Mp4)),
+            (b"\x00\x00\x00\x20ftypM4A ", Some(MediaType::M4a)),
+            (b"OggS\x00\x02", Some(MediaType::Ogg)),
+            (b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81\x01\x42\x82\x84webm", Some(MediaType::WebM)),
+            (b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81\x01\x42\x82\x88matroska", None),
+            (b"RIFF\x24\x00\x00\x00AVI LIST", None),
+            (b"GIF89a", None),
+            (b"", None),
+        ];
+
+        for (head, expected) in cases {
+            assert_eq!(sniff(head), *expected, "{:?}", head);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Minimal RIFF/WAVE header parsing
+use std::io::{self, Read, Seek, SeekFrom};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WavInfo {
+    /// Encoding of the samples, `WAVE_FORMAT_EXTENSIBLE` files report their
+    /// sub format.
+    pub format: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    /// Offset of the first sample from the start of the file.
+    pub data_offset: u64,
+    /// Length of the sample data in bytes.
+    pub data_len: u64,
+}
+
+impl WavInfo {
+    pub fn duration(&self) -> f64 {
+        let bytes_per_second =
+            self.sample_rate as u64 * self.channels as u64 * (self.bits_per_sample as u64 / 8);
+        if bytes_per_second == 0 {
+            return 0.0;
+        }
+        self.data_len as f64 / bytes_per_second as f64
+    }
+}
+
+pub const WAVE_FORMAT_PCM: u16 = 1;
+pub const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Reads the `fmt ` and `data` chunk headers without reading the samples.
+pub fn read_wav_info<R: Read + Seek>(r: &mut R) -> io::Result<WavInfo> {
+    let mut riff = [0u8; 12];
+    r.read_exact(&mut riff)?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err(invalid("not a RIFF/WAVE file"));
+    }
+
+    let mut fmt = None;
+    let mut offset = 12u64;
+    loop {
+        let mut header = [0u8; 8];
+        r.read_exact(&mut header)?;
+        let id = &header[0..4];
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+        offset += 8;
+
+        if id == b"fmt " {
+            if len < 16 {
+                return Err(invalid("fmt chunk is too short"));
+            }
+            let mut body = [0u8; 26];
+            let read = len.min(26) as usize;
+            r.read_exact(&mut body[..read])?;
+            let mut format = u16::from_le_bytes([body[0], body[1]]);
+            if format == WAVE_FORMAT_EXTENSIBLE && read == 26 {
+                format = u16::from_le_bytes([body[24], body[25]]);
+            }
+            fmt = Some((
+                format,
+                u16::from_le_bytes([body[2], body[3]]),
+                u32::from_le_bytes([body[4], body[5], body[6], body[7]]),
+                u16::from_le_bytes([body[14], body[15]]),
+            ));
+            r.seek(SeekFrom::Current((len - read as u64 + len % 2) as i64))?;
+        } else if id == b"data" {
+            let Some((format, channels, sample_rate, bits_per_sample)) = fmt else {
+                return Err(invalid("data chunk precedes fmt chunk"));
+            };
+            if sample_rate == 0 {
+                return Err(invalid("sample rate is zero"));
+            }
+            return Ok(WavInfo {
+                format,
+                channels,
+                sample_rate,
+                bits_per_sample,
+                data_offset: offset,
+                data_len: len,
+            });
+        } else {
+            // Chunks are padded to an even length
+            r.seek(SeekFrom::Current((len + len % 2) as i64))?;
+        }
+        offset += len + len % 2;
+    }
+}
+
+/// Decodes the samples of an integer PCM or float WAV file into one channel
+/// per `Vec`, scaled to -1.0..=1.0.
+pub fn read_wav_samples(wav: &[u8]) -> io::Result<(WavInfo, Vec<Vec<f32>>)> {
+    let info = read_wav_info(&mut io::Cursor::new(wav))?;
+    let start = info.data_offset as usize;
+    let end = start.saturating_add(info.data_len as usize).min(wav.len());
+    let data = wav.get(start..end).ok_or_else(|| invalid("data chunk is out of bounds"))?;
+
+    let decode: fn(&[u8]) -> f32 = match (info.format, info.bits_per_sample) {
+        (WAVE_FORMAT_PCM, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+        (WAVE_FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+        (WAVE_FORMAT_PCM, 24) => |b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2147483648.0,
+        (WAVE_FORMAT_PCM, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0,
+        (WAVE_FORMAT_IEEE_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        (WAVE_FORMAT_IEEE_FLOAT, 64) => {
+            |b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32
+        }
+        _ => return Err(invalid("unsupported sample encoding")),
+    };
+
+    let channels = info.channels.max(1) as usize;
+    let width = info.bits_per_sample as usize / 8;
+    let mut samples = vec![Vec::with_capacity(data.len() / width / channels); channels];
+    for frame in data.chunks_exact(width * channels) {
+        for (channel, sample) in frame.chunks_exact(width).enumerate() {
+            samples[channel].push(decode(sample));
+        }
+    }
+
+    Ok((info, samples))
+}
+
+/// Encodes mono samples in -1.0..=1.0 as a 16 bit PCM WAV file.
+pub fn write_mono_pcm16_wav(sample_rate: u32, samples: &[f32]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * 32767.0).round() as i16;
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+pub(crate) fn pcm16_wav(channels: u16, sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+    wav.extend_from_slice(&(channels * 2).to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn wav_info_is_parsing() {
+        let wav = pcm16_wav(2, 8000, &[0; 16000]);
+
+        let info = read_wav_info(&mut Cursor::new(wav)).unwrap();
+
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.sample_rate, 8000);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.data_offset, 44);
+        assert_eq!(info.duration(), 1.0);
+    }
+
+    #[test]
+    fn samples_are_decoded_per_channel() {
+        let wav = pcm16_wav(2, 8000, &[16384, -32768, 0, 32767]);
+
+        let (_, samples) = read_wav_samples(&wav).unwrap();
+
+        assert_eq!(samples, vec![vec![0.5, 0.0], vec![-1.0, 32767.0 / 32768.0]]);
+
+        let (info, mono) = read_wav_samples(&write_mono_pcm16_wav(16000, &[0.5, -1.0])).unwrap();
+        assert_eq!((info.channels, info.sample_rate), (1, 16000));
+        assert_eq!(mono, vec![vec![16384.0 / 32768.0, -32767.0 / 32768.0]]);
+    }
+
+    #[test]
+    fn non_wav_is_rejected() {
+        assert!(read_wav_info(&mut Cursor::new(b"ID3\x04 not a wav file".to_vec())).is_err());
+        assert!(read_wav_samples(&pcm16_wav(1, 0, &[0; 16])).is_err());
+    }
+}